@@ -1,39 +1,245 @@
+use core::f32::consts::PI;
+
+use serde::{Serialize, Deserialize};
 use syunit::*;
 
+// Submodules
+    /// Robot configuration files (".conf.json")
+    pub mod json;
+    pub use json::JsonConfig;
+// 
+
 // Angle Configuration
     /// Angle configuration (phi to gamma conversion), allowing 
     /// - `offset`: The `Phi` value has an offset compared to the `Gamma` value
     /// - `counter`: The `Phi` value is working as a counter angle to `Gamma` (The `Gamma` value will be negated)
-    #[derive(Debug, Default, Clone, Copy)]
+    /// - `ratio`: The `Gamma` value is scaled compared to the `Phi` value (gear ratios, lead screws)
+    /// - `table`: Nonlinear mapping between both values (e.g. crank-driven joints)
+    /// - `backlash`: Play of the transmission, compensated when the approach direction is known
+    /// 
+    /// The `Phi` value is calculated as `phi = table(sign * gamma / ratio) + offset`, `sign` being `-1` for counter
+    /// angles. All parts of the conversion are invertible
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
     pub struct AngleConfig {
         /// Offset of the value
         pub offset : Delta,
         /// Wheiter or not the angle is a counterpart (negative addition)
-        pub counter : bool
+        pub counter : bool,
+        /// `Gamma` distance per `Phi` distance, e.g. the gear ratio or `2 * PI / pitch` for lead screws
+        #[serde(default = "default_ratio")]
+        pub ratio : f32,
+        /// Lookup table of (scaled `Gamma`, `Phi`) pairs, see `LookupTable`. An empty table maps the values unchanged
+        #[serde(default, skip_serializing_if = "LookupTable::is_empty")]
+        pub table : LookupTable,
+        /// Total play of the transmission (as `Gamma` distance), see `gamma_from_phi_dir()`
+        #[serde(default)]
+        pub backlash : Delta
+    }
+
+    fn default_ratio() -> f32 {
+        1.0
+    }
+
+    /// Lookup table of (scaled `Gamma`, `Phi`) pairs with up to `LookupTable::MAX_LEN` entries, values in between are 
+    /// interpolated linearly, values outside are extrapolated with the first or last segment
+    /// 
+    /// The table is stored inline, so `AngleConfig` stays `Copy`. Both columns are strictly monotonic, the first one
+    /// increasing, which is checked when the table is created (or deserialized as list of pairs)
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    #[serde(try_from = "Vec<[f32; 2]>", into = "Vec<[f32; 2]>")]
+    pub struct LookupTable {
+        len : usize,
+        entries : [[f32; 2]; Self::MAX_LEN]
+    }
+
+    impl LookupTable {
+        /// Maximum number of entries of a table
+        pub const MAX_LEN : usize = 32;
+
+        /// An empty table, mapping the values unchanged
+        pub const EMPTY : Self = Self { len: 0, entries: [[0.0; 2]; Self::MAX_LEN] };
+
+        /// Creates a new table out of the given entries, the table must be empty or have between two and `MAX_LEN` 
+        /// entries, both columns must be strictly monotonic, the first one increasing
+        pub fn new(entries : &[[f32; 2]]) -> Result<Self, crate::Error> {
+            if entries.len() == 1 {
+                return Err("A lookup table requires at least two entries".into());
+            }
+
+            if entries.len() > Self::MAX_LEN {
+                return Err(format!("A lookup table can have at most {} entries (entries: {})", Self::MAX_LEN, entries.len()).into());
+            }
+
+            if !entries.windows(2).all(|w| w[1][0] > w[0][0]) {
+                return Err("The first column of the lookup table must be strictly increasing".into());
+            }
+
+            let increasing = entries.windows(2).all(|w| w[1][1] > w[0][1]);
+            let decreasing = entries.windows(2).all(|w| w[1][1] < w[0][1]);
+
+            if !increasing && !decreasing {
+                return Err("The second column of the lookup table must be strictly monotonic".into());
+            }
+
+            let mut table = Self::EMPTY;
+            table.entries[.. entries.len()].copy_from_slice(entries);
+            table.len = entries.len();
+            Ok(table)
+        }
+
+        /// The entries of the table
+        pub fn entries(&self) -> &[[f32; 2]] {
+            &self.entries[.. self.len]
+        }
+
+        /// Number of entries in the table
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        /// Returns `true` if the table has no entries
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+    }
+
+    impl Default for LookupTable {
+        fn default() -> Self {
+            Self::EMPTY
+        }
+    }
+
+    impl TryFrom<Vec<[f32; 2]>> for LookupTable {
+        type Error = crate::Error;
+
+        fn try_from(entries : Vec<[f32; 2]>) -> Result<Self, Self::Error> {
+            Self::new(&entries)
+        }
+    }
+
+    impl From<LookupTable> for Vec<[f32; 2]> {
+        fn from(table : LookupTable) -> Self {
+            table.entries().to_vec()
+        }
+    }
+
+    /// Interpolates the lookup table, mapping the values of the column `from` onto the other column
+    fn interpolate(table : &[[f32; 2]], value : f32, from : usize) -> f32 {
+        if table.len() < 2 {
+            return value;
+        }
+
+        let to = 1 - from;
+        let asc = table[table.len() - 1][from] > table[0][from];
+
+        let index = table.windows(2)
+            .position(|w| if asc { value <= w[1][from] } else { value >= w[1][from] })
+            .unwrap_or(table.len() - 2);
+
+        let (a, b) = (table[index], table[index + 1]);
+        a[to] + (value - a[from]) * (b[to] - a[to]) / (b[from] - a[from])
     }
 
     impl AngleConfig {
         /// An empty `AngleConfig`, with all values set to `0`/´false`
-        pub const EMPTY : Self = Self { offset: Delta::ZERO, counter: false };
+        pub const EMPTY : Self = Self { offset: Delta::ZERO, counter: false, ratio: 1.0, table: LookupTable::EMPTY, backlash: Delta::ZERO };
+
+        /// Sets the ratio between `Gamma` and `Phi` distances (e.g. a gear ratio)
+        pub fn with_ratio(mut self, ratio : f32) -> Self {
+            self.ratio = ratio;
+            self
+        }
+
+        /// Sets the ratio for a lead screw with the given pitch (`Phi` distance per revolution of the screw)
+        pub fn with_lead(self, pitch : f32) -> Self {
+            self.with_ratio(2.0 * PI / pitch)
+        }
+
+        /// Sets the lookup table, see `AngleConfig::table`
+        pub fn with_table(mut self, table : LookupTable) -> Self {
+            self.table = table;
+            self
+        }
+
+        /// Sets the backlash of the transmission
+        pub fn with_backlash(mut self, backlash : Delta) -> Self {
+            self.backlash = backlash;
+            self
+        }
+
+        /// Checks if the configuration is invertible (the lookup table is checked when it is created)
+        pub fn validate(&self) -> Result<(), crate::Error> {
+            if !self.ratio.is_finite() || (self.ratio == 0.0) {
+                return Err(format!("The ratio must be a finite value other than zero (ratio: {})", self.ratio).into());
+            }
+
+            if self.backlash.0 < 0.0 {
+                return Err(format!("The backlash must not be negative (backlash: {})", self.backlash.0).into());
+            }
+
+            Ok(())
+        }
+
+        /// Scales all `Phi` distances of the configuration by the given factor, e.g. to convert the units of a linear 
+        /// axis
+        pub fn scale_phi(&mut self, factor : f32) {
+            self.offset = self.offset * factor;
+
+            if self.table.is_empty() {
+                self.ratio /= factor;
+            } else {
+                let len = self.table.len;
+                self.table.entries[.. len].iter_mut().for_each(|entry| entry[1] *= factor);
+            }
+        }
+
+        /// Converts the given `Phi` velocity into a `Gamma` velocity, for lookup tables the steepest segment is used, so
+        /// the `Phi` velocity is not exceeded anywhere
+        pub fn gamma_velocity(&self, vel : Velocity) -> Velocity {
+            let slope = self.table.entries().windows(2)
+                .map(|w| ((w[1][1] - w[0][1]) / (w[1][0] - w[0][0])).abs())
+                .fold(None, |max : Option<f32>, s| Some(max.map_or(s, |m| m.max(s))))
+                .unwrap_or(1.0);
+
+            Velocity(vel.0 * self.ratio.abs() / slope)
+        }
 
         /// Convert the given gamma angle to a phi angle
         pub fn phi_from_gamma(&self, gamma : Gamma) -> Phi {
-            (if self.counter { 
-                -gamma
-            } else { 
-                gamma
-            } + self.offset).force_to_phi()
+            let scaled = if self.counter { -gamma.0 } else { gamma.0 } / self.ratio;
+            Phi(interpolate(self.table.entries(), scaled, 0)) + self.offset
         }
         
         /// Convert the given phi angle to a gamma angle
         pub fn gamma_from_phi(&self, phi : Phi) -> Gamma {
-            if self.counter { 
-                -phi.force_to_gamma() + self.offset
-            } else { 
-                phi.force_to_gamma() - self.offset
+            let scaled = interpolate(self.table.entries(), (phi - self.offset).0, 1) * self.ratio;
+            Gamma(if self.counter { -scaled } else { scaled })
+        }
+
+        /// Converts the given phi angle to the gamma angle that has to be driven to when approaching it in the given
+        /// direction (`CW` for increasing gamma values), compensating the backlash
+        pub fn gamma_from_phi_dir(&self, phi : Phi, dir : Direction) -> Gamma {
+            match dir {
+                Direction::CW => self.gamma_from_phi(phi) + self.backlash / 2.0,
+                Direction::CCW => self.gamma_from_phi(phi) - self.backlash / 2.0
+            }
+        }
+
+        /// Converts the given gamma angle, that has been approached in the given direction, to a phi angle, 
+        /// compensating the backlash
+        pub fn phi_from_gamma_dir(&self, gamma : Gamma, dir : Direction) -> Phi {
+            match dir {
+                Direction::CW => self.phi_from_gamma(gamma - self.backlash / 2.0),
+                Direction::CCW => self.phi_from_gamma(gamma + self.backlash / 2.0)
             }
         }
     }
+
+    impl Default for AngleConfig {
+        fn default() -> Self {
+            Self::EMPTY
+        }
+    }
 //
 
 // AxisConf
@@ -55,4 +261,134 @@ use syunit::*;
             Ok(())
         }
     }
-// 
\ No newline at end of file
+
+    /// How a descriptor selects one of multiple solutions of its inverse kinematics
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum BranchSelection {
+        /// The solution is selected by the flags of the configuration
+        #[default]
+        Flags,
+        /// The solution closest to the reference `Phi` values is selected (flags and turns are ignored)
+        Nearest
+    }
+
+    /// Shifts the given angle by full turns, so that it lies as close as possible to the reference angle
+    pub fn unwrap_phi(phi : Phi, reference : Phi) -> Phi {
+        Phi(phi.0 - ((phi.0 - reference.0) / 2.0 / PI).round() * 2.0 * PI)
+    }
+
+    /// Selects the set of `Phi` values closest to the reference out of all the given solutions, all joints are treated
+    /// as revolute joints and are unwrapped to the reference (see `unwrap_phi()`)
+    pub fn nearest_phis<const C : usize, I>(solutions : I, reference : &[Phi; C]) -> Option<[Phi; C]> 
+    where
+        I : IntoIterator<Item = [Phi; C]>
+    {
+        solutions.into_iter()
+            .map(|mut phis| {
+                for (phi, refr) in phis.iter_mut().zip(reference) {
+                    *phi = unwrap_phi(*phi, *refr);
+                }
+
+                let dist : f32 = phis.iter().zip(reference).map(|(phi, refr)| (phi.0 - refr.0).powi(2)).sum();
+                (phis, dist)
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(phis, _)| phis)
+    }
+// 
+
+// Arm configuration
+    /// The shoulder configuration of an articulated arm
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Shoulder {
+        /// The wrist lies in front of the first axis
+        #[default]
+        Front,
+        /// The base is turned by 180° and the arm reaches over its back
+        Back
+    }
+
+    /// The elbow configuration of an articulated arm
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Elbow {
+        /// The elbow lies above the line between shoulder and wrist
+        #[default]
+        Up,
+        /// The elbow lies below the line between shoulder and wrist
+        Down
+    }
+
+    /// The wrist configuration of an articulated arm
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Wrist {
+        /// The wrist pitch angle is positive
+        #[default]
+        NoFlip,
+        /// The wrist pitch angle is negative, the wrist roll axes are turned by 180°
+        Flip
+    }
+
+    /// Axis configuration of an articulated arm, selecting one of the (up to 8) solutions of the inverse kinematics
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    pub struct ArmConfig {
+        /// Configuration of the shoulder
+        pub shoulder : Shoulder,
+        /// Configuration of the elbow
+        pub elbow : Elbow,
+        /// Configuration of the wrist
+        pub wrist : Wrist,
+        /// Number of full turns added to each joint, allows joints to reach angles beyond ±180°
+        #[serde(default)]
+        pub turns : Vec<i32>,
+        /// The way a solution is selected
+        #[serde(default)]
+        pub selection : BranchSelection,
+
+        /// Reference joint values of `BranchSelection::Nearest`, runtime state that is not stored with the configuration
+        #[serde(skip)]
+        phis : Vec<Phi>
+    }
+
+    impl ArmConfig {
+        /// Creates a new arm configuration out of the given flags
+        pub fn new(shoulder : Shoulder, elbow : Elbow, wrist : Wrist) -> Self {
+            Self { shoulder, elbow, wrist, turns: Vec::new(), selection: BranchSelection::Flags, phis: Vec::new() }
+        }
+
+        /// Creates a new arm configuration that always selects the solution nearest to the reference `Phi` values 
+        /// (the values given to `configure()` or the current values of the robot if none have been configured)
+        pub fn nearest() -> Self {
+            Self { selection: BranchSelection::Nearest, ..Default::default() }
+        }
+
+        /// Sets the number of full turns for each joint
+        pub fn with_turns(mut self, turns : Vec<i32>) -> Self {
+            self.turns = turns;
+            self
+        }
+
+        /// Returns the number of full turns of the joint with the given index
+        #[inline]
+        pub fn turns(&self, index : usize) -> i32 {
+            self.turns.get(index).copied().unwrap_or(0)
+        }
+
+        /// Adds the configured full turns to the given `Phi` values
+        pub fn apply_turns(&self, phis : &mut [Phi]) {
+            for (i, phi) in phis.iter_mut().enumerate() {
+                *phi += Delta(self.turns(i) as f32 * 2.0 * PI);
+            }
+        }
+    }
+
+    impl AxisConfig for ArmConfig {
+        fn phis(&self) -> &[Phi] {
+            &self.phis
+        }
+
+        fn configure(&mut self, phis : Vec<Phi>) -> Result<(), crate::Error> {
+            self.phis = phis;
+            Ok(())
+        }
+    }
+// 
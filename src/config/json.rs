@@ -0,0 +1,404 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use glam::Vec3;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use syact::{StepperConfig, StepperConst};
+use syact::act::stepper::{StepperActuator, StepperActuatorGroup};
+use syact::math::movements::DefinedActuator;
+use syunit::*;
+
+use crate::Robot;
+use crate::config::AngleConfig;
+use crate::desc::{KinElement, Movement, SerialKinematic};
+use crate::desc::common::SerialDescriptor;
+use crate::rcs::{LengthUnit, PointRef, Position, WorldObj};
+use crate::robs::{StepperRobot, Tool};
+
+// Components
+    /// Electrical values shared by all stepper motors of the robot
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct LkConfig {
+        /// Supply voltage of the motors in Volts
+        pub u : f32,
+        /// Safety factor applied to the loads of the motors
+        #[serde(default)]
+        pub s_f : Option<f32>
+    }
+
+    /// Simulation values of a component
+    #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+    pub struct SimConfig {
+        /// Mass moved by the component
+        #[serde(default)]
+        pub mass : f32,
+        /// Friction of the component
+        #[serde(default)]
+        pub fric : f32
+    }
+
+    /// Measurement (homing) values of a component
+    #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+    pub struct MeasConfig {
+        /// Pin of the switch used for the measurement
+        #[serde(default)]
+        pub pin : Option<u32>,
+        /// The `Gamma` value of the component when the switch is triggered
+        #[serde(default)]
+        pub set_val : f32,
+        /// Maximum `Gamma` distance driven while searching the switch
+        #[serde(default)]
+        pub dist : f32
+    }
+
+    /// Limits of a component, given as `Phi` values (as used by the kinematics)
+    #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+    pub struct LimitConfig {
+        /// Maximum velocity
+        #[serde(default)]
+        pub vel : Option<f32>,
+        /// Minimum `Phi` value
+        #[serde(default)]
+        pub min : Option<f32>,
+        /// Maximum `Phi` value
+        #[serde(default)]
+        pub max : Option<f32>
+    }
+
+    /// The configuration of a single component (an axis of the robot)
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct CompConfig {
+        /// Name of the component, also used as name of its point in the `WorldObj` tree
+        pub name : String,
+        /// Name of the type of the component (e.g. `"syact::act::gear_bearing::GearJoint"`)
+        pub type_name : String,
+        /// Whether the component moves linearly along its axis, by default components are linear if their type is a
+        /// `LinearAxis`
+        #[serde(default)]
+        pub linear : Option<bool>,
+        /// Type specific data used to construct the component, see `JsonConfig::build_robot()`
+        #[serde(default)]
+        pub obj : Value,
+        /// Conversion between the `Phi` and `Gamma` values of the component
+        #[serde(default)]
+        pub ang : AngleConfig,
+        /// Simulation values
+        #[serde(default)]
+        pub sim : Option<SimConfig>,
+        /// Measurement values
+        #[serde(default)]
+        pub meas : Option<MeasConfig>,
+        /// Limits of the component
+        #[serde(default)]
+        pub limit : LimitConfig
+    }
+
+    impl CompConfig {
+        /// Returns whether the component moves linearly (`true`) or rotates around its axis (`false`)
+        pub fn is_linear(&self) -> bool {
+            self.linear.unwrap_or_else(|| {
+                self.type_name.rsplit("::").next().unwrap_or_default().starts_with("LinearAxis")
+            })
+        }
+    }
+//
+
+/// Lower and upper limits of all components
+pub type CompLimits<U, const C : usize> = ([Option<U>; C], [Option<U>; C]);
+
+/// Names of the stepper motor constants that can be referred to in configuration files, see `stepper_const()`
+pub const STEPPER_CONST_NAMES : [&str; 2] = [ "GEN", "MOT_17HE15_1504S" ];
+
+/// Returns the stepper motor constants with the given name (one of `STEPPER_CONST_NAMES`, e.g. `"MOT_17HE15_1504S"`),
+/// used to refer to motors in configuration files
+pub fn stepper_const(name : &str) -> Result<StepperConst, crate::Error> {
+    match name {
+        "GEN" => Ok(StepperConst::GEN),
+        "MOT_17HE15_1504S" => Ok(StepperConst::MOT_17HE15_1504S),
+        _ => Err(format!("Unknown stepper motor '{}', valid names are: {}", name, STEPPER_CONST_NAMES.join(", ")).into())
+    }
+}
+
+/// A robot configuration file (".conf.json"), see "docs/configuration.md"
+///
+/// The kinematic chain starts at the `anchor`, each component rotates around (or moves along) its entry in `axes`,
+/// the entry in `dims` is the offset to the next component (or to the TCP for the last component)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JsonConfig {
+    /// Name of the robot
+    pub name : String,
+    /// Version of the configuration
+    #[serde(default)]
+    pub conf_version : String,
+    /// Author of the configuration
+    #[serde(default)]
+    pub author : String,
+
+    /// Unit of all lengths in the file, the configuration is converted into millimetres when parsed
+    #[serde(default)]
+    pub unit : LengthUnit,
+
+    /// Electrical values of the motors
+    #[serde(default)]
+    pub lk : Option<LkConfig>,
+
+    /// Position of the first component relative to the base of the robot
+    pub anchor : [f32; 3],
+    /// Offsets between the components
+    pub dims : Vec<[f32; 3]>,
+    /// Axes of the components
+    pub axes : Vec<[f32; 3]>,
+
+    /// The components of the robot
+    pub comps : Vec<CompConfig>
+}
+
+impl JsonConfig {
+    // Parsing
+        /// Parses and validates the given configuration, all lengths are converted into millimetres
+        pub fn parse(json : &str) -> Result<Self, crate::Error> {
+            let de = &mut serde_json::Deserializer::from_str(json);
+            let mut conf : Self = serde_path_to_error::deserialize(de)
+                .map_err(|err| format!("Bad configuration at '{}': {}", err.path(), err.inner()))?;
+
+            conf.validate()?;
+            conf.convert_unit(LengthUnit::Millimetre);
+
+            Ok(conf)
+        }
+
+        /// Reads the given configuration file, see `JsonConfig::parse()`
+        pub fn read_from_file<P : AsRef<Path>>(path : P) -> Result<Self, crate::Error> {
+            let path = path.as_ref();
+            let content = fs::read_to_string(path)
+                .map_err(|err| format!("Failed to read configuration file '{}': {}", path.display(), err))?;
+
+            Self::parse(&content).map_err(|err| format!("In configuration file '{}': {}", path.display(), err).into())
+        }
+
+        /// Checks the configuration for consistency
+        pub fn validate(&self) -> Result<(), crate::Error> {
+            if (self.dims.len() != self.comps.len()) || (self.axes.len() != self.comps.len()) {
+                return Err(format!("The configuration has {} components, but {} dims and {} axes",
+                    self.comps.len(), self.dims.len(), self.axes.len()).into());
+            }
+
+            let mut names = HashSet::new();
+
+            for (comp, axis) in self.comps.iter().zip(&self.axes) {
+                WorldObj::check_name(&comp.name)
+                    .map_err(|err| format!("Bad component name '{}': {}", comp.name, err))?;
+
+                if !names.insert(comp.name.as_str()) {
+                    return Err(format!("The component name '{}' is used multiple times", comp.name).into());
+                }
+
+                comp.ang.validate()
+                    .map_err(|err| format!("Bad angle configuration of the component '{}': {}", comp.name, err))?;
+
+                if comp.type_name.is_empty() {
+                    return Err(format!("The component '{}' has no type name", comp.name).into());
+                }
+
+                if Vec3::from(*axis).length() < 1e-6 {
+                    return Err(format!("The axis of the component '{}' must not be zero", comp.name).into());
+                }
+
+                if let (Some(min), Some(max)) = (comp.limit.min, comp.limit.max) {
+                    if min >= max {
+                        return Err(format!("The minimum limit of the component '{}' ({}) must be smaller than its maximum ({})",
+                            comp.name, min, max).into());
+                    }
+                }
+
+                if let Some(vel) = comp.limit.vel {
+                    if vel <= 0.0 {
+                        return Err(format!("The velocity limit of the component '{}' must be positive", comp.name).into());
+                    }
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Converts all lengths of the configuration into the given unit, including the `Phi` values of linear 
+        /// components. `Gamma` values (e.g. the measurement values) are not lengths and are kept
+        pub fn convert_unit(&mut self, unit : LengthUnit) {
+            let factor = self.unit.factor_to(unit);
+
+            if factor == 1.0 {
+                self.unit = unit;
+                return;
+            }
+
+            let scale = |v : &mut [f32; 3]| v.iter_mut().for_each(|c| *c *= factor);
+
+            scale(&mut self.anchor);
+            self.dims.iter_mut().for_each(scale);
+
+            for comp in self.comps.iter_mut().filter(|comp| comp.is_linear()) {
+                comp.ang.scale_phi(factor);
+
+                for value in [ &mut comp.limit.vel, &mut comp.limit.min, &mut comp.limit.max ].into_iter().flatten() {
+                    *value *= factor;
+                }
+
+            }
+
+            self.unit = unit;
+        }
+
+        fn check_count<const C : usize>(&self) -> Result<(), crate::Error> {
+            if self.comps.len() == C {
+                Ok(())
+            } else {
+                Err(format!("The configuration '{}' has {} components, but the robot requires {}", self.name, self.comps.len(), C).into())
+            }
+        }
+    //
+
+    // Values
+        /// The angle configurations of all components
+        pub fn ang_confs<const C : usize>(&self) -> Result<[AngleConfig; C], crate::Error> {
+            self.check_count::<C>()?;
+            Ok(core::array::from_fn(|i| self.comps[i].ang))
+        }
+
+        /// The lower and upper limits of all components as `Phi` values
+        pub fn limits<const C : usize>(&self) -> Result<CompLimits<Phi, C>, crate::Error> {
+            self.check_count::<C>()?;
+
+            Ok((
+                core::array::from_fn(|i| self.comps[i].limit.min.map(Phi)),
+                core::array::from_fn(|i| self.comps[i].limit.max.map(Phi))
+            ))
+        }
+
+        /// The lower and upper limits of all components as `Gamma` values, converted with the angle configurations
+        pub fn gamma_limits<const C : usize>(&self) -> Result<CompLimits<Gamma, C>, crate::Error> {
+            let (min, max) = self.limits::<C>()?;
+            let mut gamma_min = [None; C];
+            let mut gamma_max = [None; C];
+
+            for (i, comp) in self.comps.iter().enumerate() {
+                let lower = min[i].map(|phi| comp.ang.gamma_from_phi(phi));
+                let upper = max[i].map(|phi| comp.ang.gamma_from_phi(phi));
+
+                // Counter components (or decreasing mappings) swap their limits
+                if comp.ang.gamma_from_phi(Phi(1.0)) < comp.ang.gamma_from_phi(Phi::ZERO) {
+                    gamma_min[i] = upper;
+                    gamma_max[i] = lower;
+                } else {
+                    gamma_min[i] = lower;
+                    gamma_max[i] = upper;
+                }
+            }
+
+            Ok((gamma_min, gamma_max))
+        }
+
+        /// The velocity limits of all components as `Phi` velocities
+        pub fn velocities<const C : usize>(&self) -> Result<[Option<Velocity>; C], crate::Error> {
+            self.check_count::<C>()?;
+            Ok(core::array::from_fn(|i| self.comps[i].limit.vel.map(Velocity)))
+        }
+
+        /// The configuration of the stepper motors, if given
+        pub fn stepper_config(&self) -> Option<StepperConfig> {
+            self.lk.map(|lk| StepperConfig { voltage: lk.u, overload_current: None })
+        }
+    //
+
+    // Construction
+        /// Builds the kinematic tree of the robot, each component is a nested point named after the component, the
+        /// TCP is added as point `"tcp"` inside the last component
+        pub fn kinematic<const C : usize>(&self) -> Result<(WorldObj, SerialKinematic<C>), crate::Error> {
+            self.check_count::<C>()?;
+
+            if C == 0 {
+                return Err("The configuration must contain at least one component".into());
+            }
+
+            // Offsets of the components, the first one is placed at the anchor
+            let offsets : Vec<Vec3> = core::iter::once(self.anchor).chain(self.dims[.. C - 1].iter().copied())
+                .map(Vec3::from)
+                .collect();
+            let tcp_offset = Position::from_vec3(Vec3::from(self.dims[C - 1]));
+
+            // Build the hierarchy from the inside out
+            let mut point = PointRef::new(tcp_offset.clone());
+            let mut point_name = String::from("tcp");
+
+            for (comp, offset) in self.comps.iter().zip(&offsets).rev() {
+                point = PointRef::new(WorldObj::from_pos(Position::from_vec3(*offset)).add_point_inline(point_name, point));
+                point_name = comp.name.clone();
+            }
+
+            let world_obj = WorldObj::zero().add_point_inline(point_name, point);
+
+            let mut segments = Vec::with_capacity(C);
+            let mut path = String::new();
+
+            for (comp, axis) in self.comps.iter().zip(&self.axes) {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(&comp.name);
+
+                let movement = if comp.is_linear() {
+                    Movement::Linear(Vec3::from(*axis).normalize())
+                } else {
+                    Movement::revolute(Vec3::from(*axis), Vec3::ZERO)
+                };
+
+                segments.push(KinElement::new(movement, world_obj.req_point(path.clone())?));
+            }
+
+            let segments : [KinElement; C] = segments.try_into()
+                .map_err(|_| "Bad number of segments generated")?;
+
+            Ok((world_obj, SerialKinematic::with_tcp(segments, tcp_offset)))
+        }
+
+        /// Builds a descriptor for the robot, the limits of the components are applied to the inverse kinematics
+        pub fn descriptor<const C : usize>(&self) -> Result<SerialDescriptor<C>, crate::Error> {
+            let (world_obj, kinematic) = self.kinematic::<C>()?;
+            let (min, max) = self.limits::<C>()?;
+
+            Ok(SerialDescriptor::new(world_obj, kinematic).with_limits(min, max))
+        }
+
+        /// Builds the robot, the components are created by `build_comps` out of their configurations (the type of
+        /// the components is selected with `CompConfig::type_name`, its data is stored in `CompConfig::obj`). The
+        /// angle configurations, limits and the stepper configuration are applied to the robot
+        pub fn build_robot<G, T, F, const C : usize>(&self, build_comps : F, tools : Vec<Box<dyn Tool>>)
+            -> Result<StepperRobot<G, T, C>, crate::Error>
+        where
+            G : StepperActuatorGroup<T, C>,
+            T : StepperActuator + DefinedActuator + ?Sized + 'static,
+            F : FnOnce(&[CompConfig]) -> Result<G, crate::Error>
+        {
+            let ang_confs = self.ang_confs::<C>()?;
+            let mut comps = build_comps(&self.comps)?;
+
+            if let Some(config) = self.stepper_config() {
+                comps.set_config(config);
+            }
+
+            let mut robot = StepperRobot::try_new(ang_confs, comps, tools)?;
+
+            let (min, max) = self.gamma_limits::<C>()?;
+            robot.set_limits(&min, &max);
+
+            let velocities = self.velocities::<C>()?;
+            let current = robot.comps().velocity_max();
+            robot.set_omega_max(core::array::from_fn(|i| {
+                velocities[i].map_or(current[i], |vel| self.comps[i].ang.gamma_velocity(vel))
+            }));
+
+            Ok(robot)
+        }
+    //
+}
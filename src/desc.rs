@@ -3,8 +3,9 @@ use syact::{SyncActuatorGroup, SyncActuator};
 use syunit::*;
 
 use crate::Robot;
+use crate::robs::Tool;
 use crate::config::AxisConfig;
-use crate::rcs::{PointRef, Position, WorldObj};
+use crate::rcs::{FramedPosition, PointRef, Position, WorldObj};
 
 // ####################
 // #    SUBMODULES    #
@@ -12,11 +13,23 @@ use crate::rcs::{PointRef, Position, WorldObj};
     /// A set of commonly used descriptors
     pub mod common;
 
+    /// Calibration of kinematic parameters out of measured poses
+    pub mod calib;
+
+    mod dh;
+    pub use dh::{DhConvention, DhJoint, DhParam, DhTable};
+
     mod elem;
     pub use elem::{KinElement, Movement, Rot};
 
     mod kin;
     pub use kin::{Kinematic, SerialKinematic};
+
+    /// Reachability and dexterity maps of the workspace
+    pub mod reach;
+
+    /// Import of kinematic chains out of URDF files
+    pub mod urdf;
 // 
 
 /// # `Descriptor` trait
@@ -41,6 +54,11 @@ pub trait Descriptor<const C : usize> {
     // Calculation
         /// Returns the `Phi` values required to reach a certain position
         fn phis_for_pos(&self, pos : Position) -> Result<[Phi; C], crate::Error>;
+
+        /// Returns the `Phi` values required to reach a position given relative to a frame of the `WorldObj` tree
+        fn phis_for_framed(&self, pos : &FramedPosition) -> Result<[Phi; C], crate::Error> {
+            self.phis_for_pos(pos.resolve(self.world_obj())?)
+        }
     //
 
     // Kinematic
@@ -62,8 +80,15 @@ pub trait Descriptor<const C : usize> {
         fn tcp(&self) -> &PointRef;
     // 
 
+    // Tool
+        /// Applies the given tool to the kinematic, the TCP and `phis_for_pos()` then refer to the tip of the tool
+        fn set_tool(&mut self, tool : Option<&dyn Tool>) {
+            self.kinematic_mut().set_tool(tool.map(|t| t.tcp()).unwrap_or_default())
+        }
+    // 
+
     // Events
-        /// Updates the descriptor and its coordinate system with the given `Phi` values
+        /// Updates the descriptor and its coordinate system with the given `Phi` values and the tool equipped by the robot
         fn update<R, G, T>(&mut self, rob : &mut R, phis : &[Phi; C]) -> Result<(), crate::Error>
         where
             R : Robot<G, T, C>,
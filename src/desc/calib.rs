@@ -0,0 +1,309 @@
+use glam::Vec3;
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+use crate::config::AngleConfig;
+use crate::desc::Kinematic;
+use crate::rcs::Point;
+use crate::rcs::math::solve_linear;
+
+/// Step used for the numeric derivatives of the link offsets
+const LINK_STEP : f32 = 1e-2;
+/// Step used for the numeric derivatives of the joint offsets
+const JOINT_STEP : f32 = 1e-4;
+/// Maximum number of damping increases in a single iteration
+const MAX_DAMPING_STEPS : usize = 16;
+
+// Samples and options
+    /// A single calibration measurement, the commanded `Phi` values and the TCP position measured externally (e.g. by a
+    /// laser tracker), in base coordinates
+    #[derive(Clone, Debug)]
+    pub struct CalibSample<const C : usize> {
+        /// The commanded `Phi` values
+        pub phis : [Phi; C],
+        /// The measured position of the TCP
+        pub measured : Vec3
+    }
+
+    impl<const C : usize> CalibSample<C> {
+        /// Creates a new sample
+        pub fn new(phis : [Phi; C], measured : Vec3) -> Self {
+            Self { phis, measured }
+        }
+    }
+
+    /// Options for the calibration
+    #[derive(Clone, Debug)]
+    pub struct CalibOptions {
+        /// Whether or not the link offsets (positions of the base points of the segments) are estimated
+        pub link_offsets : bool,
+        /// Whether or not the joint zero offsets are estimated
+        pub joint_offsets : bool,
+        /// Maximum number of iterations
+        pub max_iterations : usize,
+        /// The calibration stops once the parameter step is smaller than this value
+        pub tolerance : f32
+    }
+
+    impl Default for CalibOptions {
+        fn default() -> Self {
+            Self {
+                link_offsets: true,
+                joint_offsets: true,
+                max_iterations: 50,
+                tolerance: 1e-6
+            }
+        }
+    }
+//
+
+// Report
+    /// Result of a calibration, containing the estimated corrections and the residuals before and after
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct CalibReport {
+        /// Corrections of the base point positions of each segment (in the coordinates of the parent)
+        pub link_offsets : Vec<[f32; 3]>,
+        /// Corrections of the joint zero offsets, the real joint angle equals the commanded one plus the correction
+        pub joint_offsets : Vec<Delta>,
+        /// Distance between model and measurement for every sample before the calibration
+        pub residuals_before : Vec<f32>,
+        /// Distance between model and measurement for every sample after the calibration
+        pub residuals_after : Vec<f32>,
+        /// Number of iterations performed
+        pub iterations : usize
+    }
+
+    impl CalibReport {
+        /// Root mean square of the residuals before the calibration
+        pub fn rms_before(&self) -> f32 {
+            rms(&self.residuals_before)
+        }
+
+        /// Root mean square of the residuals after the calibration
+        pub fn rms_after(&self) -> f32 {
+            rms(&self.residuals_after)
+        }
+
+        /// Largest residual before the calibration
+        pub fn max_before(&self) -> f32 {
+            self.residuals_before.iter().copied().fold(0.0, f32::max)
+        }
+
+        /// Largest residual after the calibration
+        pub fn max_after(&self) -> f32 {
+            self.residuals_after.iter().copied().fold(0.0, f32::max)
+        }
+
+        /// Applies the corrections to the given kinematic and angle configurations
+        /// 
+        /// The base points of the segments are shared with the `WorldObj` tree of the descriptor, the corrections are 
+        /// therefore applied to the tree in place (and to every other kinematic built on the same tree), so the 
+        /// corrected model can be serialized afterwards. Save or clone the tree beforehand to keep the original model
+        pub fn apply<const C : usize, K : Kinematic<C>>(&self, kinematic : &mut K, ang_confs : &mut [AngleConfig])
+        -> Result<(), crate::Error> {
+            if (self.link_offsets.len() != C) || (self.joint_offsets.len() != C) {
+                return Err(format!("The report contains corrections for a different number of joints than {}", C).into());
+            }
+
+            for (seg, offset) in kinematic.segments().iter().zip(&self.link_offsets) {
+                *seg.base().borrow_mut().pos_mut() += Vec3::from(*offset);
+            }
+
+            for (conf, offset) in ang_confs.iter_mut().zip(&self.joint_offsets) {
+                conf.offset += *offset;
+            }
+
+            let phis = kinematic.phis();
+            kinematic.update(&phis)
+        }
+    }
+
+    fn rms(values : &[f32]) -> f32 {
+        if values.is_empty() {
+            return 0.0;
+        }
+
+        (values.iter().map(|v| v * v).sum::<f32>() / values.len() as f32).sqrt()
+    }
+//
+
+/// Estimates corrections of the link offsets and joint zero offsets of a kinematic out of measured TCP positions, using
+/// a damped least squares (Levenberg-Marquardt) fit. The kinematic is left unchanged, use `CalibReport::apply()` to
+/// apply the corrections
+///
+/// Parameters that cannot be observed with the given samples (e.g. a shift along a rotation axis) are kept small by the
+/// damping, choose samples spread over the whole workspace to get meaningful values
+pub fn calibrate<const C : usize, K : Kinematic<C>>(kinematic : &mut K, samples : &[CalibSample<C>], options : &CalibOptions)
+-> Result<CalibReport, crate::Error> {
+    if samples.is_empty() {
+        return Err("No samples given for the calibration".into());
+    }
+
+    let bases : Vec<Vec3> = kinematic.segments().iter().map(|seg| seg.base().pos()).collect();
+    let phis_0 = kinematic.phis();
+
+    // The segments are modified while fitting, the original model is restored on every path
+    let report = fit(kinematic, &bases, samples, options);
+
+    restore_bases(kinematic, &bases);
+    kinematic.update(&phis_0)?;
+
+    report
+}
+
+// Helper functions
+    /// Runs the least squares fit, leaves the segments with the bases of the last evaluated parameters
+    fn fit<const C : usize, K : Kinematic<C>>(kinematic : &mut K, bases : &[Vec3], samples : &[CalibSample<C>], 
+    options : &CalibOptions) -> Result<CalibReport, crate::Error> {
+        // Indices of the parameters (3 per link offset, 1 per joint offset) that are estimated
+        let mut active = Vec::new();
+
+        if options.link_offsets {
+            active.extend(0 .. 3 * C);
+        }
+
+        if options.joint_offsets {
+            active.extend(3 * C .. 4 * C);
+        }
+
+        let mut params = vec![0.0f32; 4 * C];
+
+        let residuals_before = residuals(kinematic, bases, &params, samples)?;
+        let mut cost = cost_of(&residuals_before);
+        let mut damping = 1e-3;
+        let mut iterations = 0;
+
+        while (iterations < options.max_iterations) && !active.is_empty() {
+            iterations += 1;
+
+            let res = residuals(kinematic, bases, &params, samples)?;
+            let jacobian = jacobian(kinematic, bases, &params, samples, &active)?;
+            let n = active.len();
+
+            // Normal equations
+            let mut jtj = vec![vec![0.0f64; n]; n];
+            let mut jtr = vec![0.0f64; n];
+
+            for (row, r) in jacobian.iter().zip(&res) {
+                for i in 0 .. n {
+                    jtr[i] += row[i] * *r as f64;
+
+                    for j in 0 .. n {
+                        jtj[i][j] += row[i] * row[j];
+                    }
+                }
+            }
+
+            let mut improved = false;
+            let mut step_norm = 0.0;
+
+            for _ in 0 .. MAX_DAMPING_STEPS {
+                let mut system = jtj.clone();
+
+                for (i, row) in system.iter_mut().enumerate() {
+                    row[i] += damping * (jtj[i][i] + 1e-9);
+                }
+
+                let Some(step) = solve_linear(system, jtr.iter().map(|v| -v).collect()) else {
+                    damping *= 10.0;
+                    continue;
+                };
+
+                let mut candidate = params.clone();
+                for (idx, s) in active.iter().zip(&step) {
+                    candidate[*idx] += *s as f32;
+                }
+
+                let new_cost = cost_of(&residuals(kinematic, bases, &candidate, samples)?);
+
+                if new_cost < cost {
+                    step_norm = step.iter().map(|s| s * s).sum::<f64>().sqrt();
+                    params = candidate;
+                    cost = new_cost;
+                    damping = (damping / 10.0).max(1e-12);
+                    improved = true;
+                    break;
+                }
+
+                damping *= 10.0;
+            }
+
+            if !improved || (step_norm < options.tolerance as f64) {
+                break;
+            }
+        }
+
+        let residuals_after = residuals(kinematic, bases, &params, samples)?;
+
+        Ok(CalibReport {
+            link_offsets: (0 .. C).map(|i| [ params[3 * i], params[3 * i + 1], params[3 * i + 2] ]).collect(),
+            joint_offsets: (0 .. C).map(|i| Delta(params[3 * C + i])).collect(),
+            residuals_before: residuals_before.chunks(3).map(|r| Vec3::from_slice(r).length()).collect(),
+            residuals_after: residuals_after.chunks(3).map(|r| Vec3::from_slice(r).length()).collect(),
+            iterations
+        })
+    }
+
+    /// Sets the base points of the segments to the original values shifted by the link offsets in `params`
+    fn set_bases<const C : usize, K : Kinematic<C>>(kinematic : &mut K, bases : &[Vec3], params : &[f32]) {
+        for (i, (seg, base)) in kinematic.segments().iter().zip(bases).enumerate() {
+            *seg.base().borrow_mut().pos_mut() = *base + Vec3::from_slice(&params[3 * i .. 3 * i + 3]);
+        }
+    }
+
+    /// Resets the base points of the segments to the original values
+    fn restore_bases<const C : usize, K : Kinematic<C>>(kinematic : &mut K, bases : &[Vec3]) {
+        for (seg, base) in kinematic.segments().iter().zip(bases) {
+            *seg.base().borrow_mut().pos_mut() = *base;
+        }
+    }
+
+    /// Returns the differences between model and measurement for all samples, three values per sample
+    fn residuals<const C : usize, K : Kinematic<C>>(kinematic : &mut K, bases : &[Vec3], params : &[f32],
+    samples : &[CalibSample<C>]) -> Result<Vec<f32>, crate::Error> {
+        set_bases(kinematic, bases, params);
+
+        let mut res = Vec::with_capacity(samples.len() * 3);
+
+        for sample in samples {
+            let mut phis = sample.phis;
+            for (i, phi) in phis.iter_mut().enumerate() {
+                *phi += Delta(params[3 * C + i]);
+            }
+
+            kinematic.update(&phis)?;
+            let diff = *kinematic.calculate_end().pos() - sample.measured;
+            res.extend(diff.to_array());
+        }
+
+        Ok(res)
+    }
+
+    /// Central difference jacobian of the residuals for the active parameters
+    fn jacobian<const C : usize, K : Kinematic<C>>(kinematic : &mut K, bases : &[Vec3], params : &[f32],
+    samples : &[CalibSample<C>], active : &[usize]) -> Result<Vec<Vec<f64>>, crate::Error> {
+        let mut jac = vec![vec![0.0f64; active.len()]; samples.len() * 3];
+
+        for (col, idx) in active.iter().enumerate() {
+            let step = if *idx < 3 * C { LINK_STEP } else { JOINT_STEP };
+
+            let mut plus = params.to_vec();
+            plus[*idx] += step;
+            let mut minus = params.to_vec();
+            minus[*idx] -= step;
+
+            let res_p = residuals(kinematic, bases, &plus, samples)?;
+            let res_m = residuals(kinematic, bases, &minus, samples)?;
+
+            for (row, (p, m)) in jac.iter_mut().zip(res_p.iter().zip(&res_m)) {
+                row[col] = (*p as f64 - *m as f64) / (2.0 * step as f64);
+            }
+        }
+
+        Ok(jac)
+    }
+
+    fn cost_of(res : &[f32]) -> f64 {
+        res.iter().map(|r| (*r as f64).powi(2)).sum()
+    }
+//
@@ -7,6 +7,73 @@ use syact::math::movements::DefinedActuator;
 use syact::{SyncActuator, SyncActuatorGroup};
 use syunit::*;
 
+/// Implements the parts of `Descriptor<C>` shared by all descriptors storing their kinematic in `_kinematic`, their 
+/// tree in `_world_obj` and their axis configuration in the given field: the accessors and the `update()` event, which
+/// applies the tool of the robot and updates the kinematic
+macro_rules! descriptor_common {
+    ($c:expr, $axis_config:ident) => {
+        // Axis config
+            fn axis_config(&self) -> &Self::AxisConfig {
+                &self.$axis_config
+            }
+
+            fn axis_config_mut(&mut self) -> &mut Self::AxisConfig {
+                &mut self.$axis_config
+            }
+        // 
+
+        // Kinematic
+            fn kinematic(&self) -> &Self::Kinematic {
+                &self._kinematic
+            }
+
+            fn kinematic_mut(&mut self) -> &mut Self::Kinematic {
+                &mut self._kinematic
+            }
+        // 
+
+        // World object
+            fn world_obj(&self) -> &$crate::rcs::WorldObj {
+                &self._world_obj
+            }
+
+            fn world_obj_mut(&mut self) -> &mut $crate::rcs::WorldObj {
+                &mut self._world_obj
+            }
+
+            fn tcp(&self) -> &$crate::rcs::PointRef {
+                $crate::desc::Kinematic::tcp(&self._kinematic)
+            }
+        // 
+
+        // Events
+            fn update<R, G, T>(&mut self, rob : &mut R, phis : &[syunit::Phi; $c]) -> Result<(), $crate::Error>
+            where
+                R : $crate::Robot<G, T, $c>,
+                G : syact::SyncActuatorGroup<T, $c>,
+                T : syact::SyncActuator + syact::math::movements::DefinedActuator + ?Sized + 'static
+            {
+                self.set_tool(rob.get_tool());
+                $crate::desc::Kinematic::update(&mut self._kinematic, phis)
+            }
+        // 
+    };
+}
+
+// Submodules
+    mod belt;
+    pub use belt::{BeltLayout, BeltXYDescriptor, BeltXYZDescriptor};
+
+    mod delta;
+    pub use delta::{DeltaDescriptor, DeltaDims, DeltaKinematic};
+
+    mod serial;
+    pub use serial::{IkOptions, SerialDescriptor};
+
+    mod six_axis;
+    pub use six_axis::{ArmSolution, SixAxisDescriptor, SixAxisDims};
+// 
+
 // Linear
     pub struct LinearXYDescriptor {
         _kinematic : SerialKinematic<2>,
@@ -33,9 +100,11 @@ use syunit::*;
 
         // Calculation
             fn phis_for_pos(&self, pos : Position) -> Result<[Phi; 2], crate::Error> {
+                let end = *pos.pos() - *self._kinematic.tool().pos();
+
                 Ok([
-                    Phi(pos.x()),
-                    Phi(pos.y())
+                    Phi(end.x),
+                    Phi(end.y)
                 ])
             }
         //
@@ -65,12 +134,13 @@ use syunit::*;
         // 
 
         // Events
-            fn update<R, G, T>(&mut self, _rob : &mut R, phis : &[Phi; 2]) -> Result<(), crate::Error>
+            fn update<R, G, T>(&mut self, rob : &mut R, phis : &[Phi; 2]) -> Result<(), crate::Error>
             where
                 R : Robot<G, T, 2>,
                 G : SyncActuatorGroup<T, 2>,
                 T : SyncActuator + DefinedActuator + ?Sized + 'static
             {
+                self.set_tool(rob.get_tool());
                 self._kinematic.update(phis)
             }
         // 
@@ -89,10 +159,10 @@ use syunit::*;
 
 
             Self {
-                _kinematic: SerialKinematic::new([
+                _kinematic: SerialKinematic::with_tcp([
                     KinElement::new(Movement::Linear(Vec3::X), wobj.point("x").unwrap()),
                     KinElement::new(Movement::Linear(Vec3::Y), wobj.point("x/y").unwrap())
-                ]),
+                ], Position::zero()),
                 _world_obj: wobj,
                 __axis_config: ()
             }
@@ -126,10 +196,12 @@ use syunit::*;
 
         // Calculation
             fn phis_for_pos(&self, pos : Position) -> Result<[Phi; 3], crate::Error> {
+                let end = *pos.pos() - *self._kinematic.tool().pos();
+
                 Ok([
-                    Phi(pos.x()),
-                    Phi(pos.y()),
-                    Phi(pos.z())
+                    Phi(end.x),
+                    Phi(end.y),
+                    Phi(end.z)
                 ])
             }
         //
@@ -159,12 +231,13 @@ use syunit::*;
         // 
 
         // Events
-            fn update<R, G, T>(&mut self, _rob : &mut R, phis : &[Phi; 3]) -> Result<(), crate::Error>
+            fn update<R, G, T>(&mut self, rob : &mut R, phis : &[Phi; 3]) -> Result<(), crate::Error>
             where
                 R : Robot<G, T, 3>,
                 G : SyncActuatorGroup<T, 3>,
                 T : SyncActuator + DefinedActuator + ?Sized + 'static
             {
+                self.set_tool(rob.get_tool());
                 self._kinematic.update(phis)
             }
         // 
@@ -183,11 +256,11 @@ use syunit::*;
 
 
             Self {
-                _kinematic: SerialKinematic::new([
+                _kinematic: SerialKinematic::with_tcp([
                     KinElement::new(Movement::Linear(Vec3::X), wobj.point("x").unwrap()),
                     KinElement::new(Movement::Linear(Vec3::Y), wobj.point("x/y").unwrap()),
                     KinElement::new(Movement::Linear(Vec3::Z), wobj.point("x/y/z").unwrap())
-                ]),
+                ], Position::zero()),
                 _world_obj: wobj,
                 __axis_config: ()
             }
@@ -0,0 +1,194 @@
+use glam::{Vec2, Vec3};
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+use crate::Descriptor;
+use crate::desc::{KinElement, Kinematic, Movement, SerialKinematic};
+use crate::rcs::{Point, PointRef, Position, WorldObj};
+
+// Layout
+    /// The belt layout of a gantry where two motors combine into the X- and Y-movement
+    /// 
+    /// Both layouts share the same kinematic relation (`a = x + y` and `b = x - y`), they only differ in the way the belts
+    /// are routed (the H-bot applies a racking torque on the gantry)
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum BeltLayout {
+        /// Two crossed belts, the motors are fixed to the frame
+        #[default]
+        CoreXY,
+        /// A single H-shaped belt, the motors are fixed to the frame
+        HBot
+    }
+
+    impl BeltLayout {
+        /// Returns the movement of the TCP per unit of `Phi` of the motors `A` and `B`
+        pub fn motor_dirs(&self) -> [Vec3; 2] {
+            match self {
+                Self::CoreXY | Self::HBot => [
+                    Vec3::new(0.5, 0.5, 0.0),
+                    Vec3::new(0.5, -0.5, 0.0)
+                ]
+            }
+        }
+
+        /// Returns the `Phi` values of the motors `A` and `B` for the given XY-position
+        pub fn phis_for_xy(&self, xy : Vec2) -> [Phi; 2] {
+            match self {
+                Self::CoreXY | Self::HBot => [
+                    Phi(xy.x + xy.y),
+                    Phi(xy.x - xy.y)
+                ]
+            }
+        }
+    }
+// 
+
+// Descriptors
+    /// A belt driven XY-gantry (CoreXY or H-bot), where the motors `A` and `B` combine into the X- and Y-movement
+    pub struct BeltXYDescriptor {
+        _kinematic : SerialKinematic<2>,
+        _world_obj : WorldObj,
+
+        layout : BeltLayout,
+
+        __axis_config : ()
+    }
+
+    impl BeltXYDescriptor {
+        /// Creates a new belt descriptor with the given layout
+        pub fn new(layout : BeltLayout) -> Self {
+            let wobj = WorldObj::zero()
+                .add_point_inline("a", PointRef::new(
+                    WorldObj::zero()
+                        .add_point_inline("b", PointRef::new(Position::zero()))
+                ));
+
+            let [ dir_a, dir_b ] = layout.motor_dirs();
+
+            Self {
+                _kinematic: SerialKinematic::with_tcp([
+                    KinElement::new(Movement::Linear(dir_a), wobj.point("a").unwrap()),
+                    KinElement::new(Movement::Linear(dir_b), wobj.point("a/b").unwrap())
+                ], Position::zero()),
+                _world_obj: wobj,
+
+                layout,
+
+                __axis_config: ()
+            }
+        }
+
+        /// Creates a new CoreXY gantry
+        #[inline]
+        pub fn core_xy() -> Self {
+            Self::new(BeltLayout::CoreXY)
+        }
+
+        /// Creates a new H-bot gantry
+        #[inline]
+        pub fn h_bot() -> Self {
+            Self::new(BeltLayout::HBot)
+        }
+
+        /// Returns the belt layout of the gantry
+        #[inline]
+        pub fn layout(&self) -> BeltLayout {
+            self.layout
+        }
+    }
+
+    impl Descriptor<2> for BeltXYDescriptor {
+        // Types
+            type AxisConfig = ();
+            type Kinematic = SerialKinematic<2>;
+        // 
+
+        descriptor_common!(2, __axis_config);
+
+        // Calculation
+            fn phis_for_pos(&self, pos : Position) -> Result<[Phi; 2], crate::Error> {
+                let end = *pos.pos() - *self._kinematic.tool().pos();
+                Ok(self.layout.phis_for_xy(end.truncate()))
+            }
+        //
+    }
+
+    /// A belt driven XY-gantry (CoreXY or H-bot) with an additional Z-axis, driven by a single motor
+    pub struct BeltXYZDescriptor {
+        _kinematic : SerialKinematic<3>,
+        _world_obj : WorldObj,
+
+        layout : BeltLayout,
+
+        __axis_config : ()
+    }
+
+    impl BeltXYZDescriptor {
+        /// Creates a new belt descriptor with the given layout
+        pub fn new(layout : BeltLayout) -> Self {
+            let wobj = WorldObj::zero()
+                .add_point_inline("a", PointRef::new(
+                    WorldObj::zero()
+                        .add_point_inline("b", PointRef::new(
+                            WorldObj::zero()
+                                .add_point_inline("z", PointRef::new(Position::zero()))
+                        ))
+                ));
+
+            let [ dir_a, dir_b ] = layout.motor_dirs();
+
+            Self {
+                _kinematic: SerialKinematic::with_tcp([
+                    KinElement::new(Movement::Linear(dir_a), wobj.point("a").unwrap()),
+                    KinElement::new(Movement::Linear(dir_b), wobj.point("a/b").unwrap()),
+                    KinElement::new(Movement::Linear(Vec3::Z), wobj.point("a/b/z").unwrap())
+                ], Position::zero()),
+                _world_obj: wobj,
+
+                layout,
+
+                __axis_config: ()
+            }
+        }
+
+        /// Creates a new CoreXY gantry with a Z-axis
+        #[inline]
+        pub fn core_xy() -> Self {
+            Self::new(BeltLayout::CoreXY)
+        }
+
+        /// Creates a new H-bot gantry with a Z-axis
+        #[inline]
+        pub fn h_bot() -> Self {
+            Self::new(BeltLayout::HBot)
+        }
+
+        /// Returns the belt layout of the gantry
+        #[inline]
+        pub fn layout(&self) -> BeltLayout {
+            self.layout
+        }
+    }
+
+    impl Descriptor<3> for BeltXYZDescriptor {
+        // Types
+            type AxisConfig = ();
+            type Kinematic = SerialKinematic<3>;
+        // 
+
+        descriptor_common!(3, __axis_config);
+
+        // Calculation
+            fn phis_for_pos(&self, pos : Position) -> Result<[Phi; 3], crate::Error> {
+                let end = *pos.pos() - *self._kinematic.tool().pos();
+                let [ phi_a, phi_b ] = self.layout.phis_for_xy(end.truncate());
+
+                Ok([
+                    phi_a,
+                    phi_b,
+                    Phi(end.z)
+                ])
+            }
+        //
+    }
+// 
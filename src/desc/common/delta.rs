@@ -0,0 +1,311 @@
+use core::f32::consts::PI;
+
+use glam::{Mat3, Vec3};
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+use crate::Descriptor;
+use crate::desc::{KinElement, Kinematic, Movement, Rot};
+use crate::rcs::{Point, PointRef, Position, WorldObj};
+use crate::rcs::math::normalize_angle;
+
+// Dimensions
+    /// The dimensions of a delta robot, all arms are spaced evenly (120°) around the Z-axis, the first arm points
+    /// into the X-direction
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct DeltaDims {
+        /// Distance from the center of the base to the joints of the upper arms
+        pub base_radius : f32,
+        /// Distance from the center of the effector to the joints of the lower arms (parallelograms)
+        pub effector_radius : f32,
+        /// Length of the upper arms (driven by the motors)
+        pub upper_arm : f32,
+        /// Length of the lower arms (parallelograms)
+        pub lower_arm : f32
+    }
+
+    impl DeltaDims {
+        /// Returns the angle of the arm with the given index around the Z-axis
+        #[inline]
+        pub fn arm_angle(index : usize) -> f32 {
+            2.0 * PI / 3.0 * index as f32
+        }
+
+        /// Returns the (horizontal) direction of the arm with the given index
+        #[inline]
+        pub fn arm_dir(index : usize) -> Vec3 {
+            let angle = Self::arm_angle(index);
+            Vec3::new(angle.cos(), angle.sin(), 0.0)
+        }
+
+        /// Calculates the position of the effector center for the given `Phi` values of the upper arms
+        /// 
+        /// A positive `Phi` value turns the upper arm downwards, `Phi::ZERO` means the arm is horizontal
+        pub fn forward(&self, phis : &[Phi; 3]) -> Result<Vec3, crate::Error> {
+            // Centers of the spheres reachable by the lower arms, already shifted by the effector radius
+            let mut centers = [Vec3::ZERO; 3];
+
+            for i in 0 .. 3 {
+                centers[i] = Self::arm_dir(i) * (self.base_radius - self.effector_radius + self.upper_arm * phis[i].0.cos())
+                    - Vec3::Z * self.upper_arm * phis[i].0.sin();
+            }
+
+            // Trilateration of the three spheres (all with the radius of the lower arm)
+            let d_vec = centers[1] - centers[0];
+            let d = d_vec.length();
+
+            if d < f32::EPSILON {
+                return Err("The lower arms of the delta robot are degenerated (coincident joints)".into());
+            }
+
+            let ex = d_vec / d;
+            let i = ex.dot(centers[2] - centers[0]);
+            let ey_raw = centers[2] - centers[0] - ex * i;
+
+            if ey_raw.length() < f32::EPSILON {
+                return Err("The lower arms of the delta robot are degenerated (collinear joints)".into());
+            }
+
+            let ey = ey_raw.normalize();
+            let mut ez = ex.cross(ey);
+            let j = ey.dot(centers[2] - centers[0]);
+
+            let x = d / 2.0;
+            let y = (i.powi(2) + j.powi(2)) / 2.0 / j - i / j * x;
+            let z_sq = self.lower_arm.powi(2) - x.powi(2) - y.powi(2);
+
+            if z_sq < 0.0 {
+                return Err(format!("The given phis ({:?}) cannot be reached by the lower arms", phis).into());
+            }
+
+            // The effector always hangs below the arms
+            if ez.z > 0.0 {
+                ez = -ez;
+            }
+
+            Ok(centers[0] + ex * x + ey * y + ez * z_sq.sqrt())
+        }
+
+        /// Calculates the `Phi` values of the upper arms required to move the effector center to the given position, 
+        /// always choosing the solution with the elbows pointing outwards
+        pub fn inverse(&self, pos : Vec3) -> Result<[Phi; 3], crate::Error> {
+            let mut phis = [Phi::ZERO; 3];
+
+            for (i, phi) in phis.iter_mut().enumerate() {
+                let u = Self::arm_dir(i);
+                let v = Vec3::Z.cross(u);
+
+                // Position of the lower arm joint relative to the upper arm joint in the arm plane
+                let s = pos.dot(u) + self.effector_radius - self.base_radius;
+                let t = pos.dot(v);
+                let z = pos.z;
+
+                // a * cos(phi) + b * sin(phi) = k
+                let a = -2.0 * s * self.upper_arm;
+                let b = 2.0 * z * self.upper_arm;
+                let k = self.lower_arm.powi(2) - self.upper_arm.powi(2) - s.powi(2) - t.powi(2) - z.powi(2);
+                let norm = (a.powi(2) + b.powi(2)).sqrt();
+
+                if (norm < f32::EPSILON) || (k.abs() > norm) {
+                    return Err(format!("The position {:?} is out of reach for arm {} of the delta robot", pos, i).into());
+                }
+
+                let base = b.atan2(a);
+                let spread = (k / norm).acos();
+
+                let phi_a = normalize_angle(base + spread);
+                let phi_b = normalize_angle(base - spread);
+
+                *phi = Phi(if phi_a.cos() >= phi_b.cos() { phi_a } else { phi_b });
+            }
+
+            Ok(phis)
+        }
+    }
+// 
+
+// Kinematic
+    /// Parallel kinematic of a delta robot, the three `KinElement`s represent the upper arms, the TCP is the center of the 
+    /// effector, which is calculated by the closed-form forward kinematics
+    #[derive(Debug)]
+    pub struct DeltaKinematic {
+        dims : DeltaDims,
+        segments : [KinElement; 3],
+        tool : Position,
+        tcp : PointRef
+    }
+
+    impl DeltaKinematic {
+        /// Creates a new delta kinematic out of the dimensions and the arm joints, `tcp` is the effector point that 
+        /// will be updated with every call to `update()`
+        pub fn new(dims : DeltaDims, segments : [KinElement; 3], tcp : PointRef) -> Self {
+            Self { dims, segments, tool: Position::zero(), tcp }
+        }
+
+        /// Returns the dimensions of the delta robot
+        #[inline]
+        pub fn dims(&self) -> &DeltaDims {
+            &self.dims
+        }
+
+        /// Calculates the TCP out of the current `Phi` values, returns an error if the values cannot be reached by the 
+        /// lower arms (possible only if the segments have been changed without calling `update()`)
+        pub fn try_calculate_end(&self) -> Result<Position, crate::Error> {
+            let pos = self.dims.forward(&self.phis())?;
+            Ok(Position::new_ori(pos + *self.tool.pos(), *self.tool.ori()))
+        }
+    }
+
+    impl Kinematic<3> for DeltaKinematic {
+        // Segments
+            fn segments(&self) -> &[KinElement; 3] {
+                &self.segments
+            }
+
+            fn segments_mut(&mut self) -> &mut [KinElement; 3] {
+                &mut self.segments
+            }
+
+            fn tcp(&self) -> &PointRef {
+                &self.tcp
+            }
+
+            fn tcp_mut(&mut self) -> &mut PointRef {
+                &mut self.tcp
+            }
+        // 
+
+        // Tool
+            fn tool(&self) -> &Position {
+                &self.tool
+            }
+
+            fn set_tool(&mut self, tool : Position) {
+                self.tool = tool;
+            }
+        // 
+
+        /// Calculates the TCP out of the current `Phi` values, see `try_calculate_end()`
+        /// 
+        /// # Panics
+        /// 
+        /// If the current `Phi` values cannot be reached by the lower arms
+        fn calculate_end(&self) -> Position {
+            self.try_calculate_end().unwrap_or_else(|err| panic!("Invalid state of the delta kinematic: {}", err))
+        }
+
+        // Events
+            fn update(&mut self, phis : &[Phi; 3]) -> Result<(), crate::Error> {
+                let end = self.dims.forward(phis)?;
+
+                for (segment, phi) in self.segments.iter_mut().zip(phis) {
+                    segment.update(*phi)?;
+                }
+
+                let mut tcp = self.tcp.borrow_mut();
+                *tcp.pos_mut() = end + *self.tool.pos();
+                *tcp.ori_mut() = *self.tool.ori();
+
+                Ok(())
+            }
+        // 
+    }
+// 
+
+// Descriptor
+    /// A delta robot with three parallelogram arms, the origin of the coordinate system lies in the center of the base, 
+    /// the effector moves in the negative Z-direction
+    pub struct DeltaDescriptor {
+        _kinematic : DeltaKinematic,
+        _world_obj : WorldObj,
+
+        phi_min : Phi,
+        phi_max : Phi,
+
+        __axis_config : ()
+    }
+
+    impl DeltaDescriptor {
+        /// Creates a new delta descriptor out of the given dimensions
+        pub fn new(dims : DeltaDims) -> Self {
+            let mut wobj = WorldObj::zero();
+
+            for i in 0 .. 3 {
+                wobj = wobj.add_point_inline(format!("arm_{}", i), PointRef::new(WorldObj::from_pos(Position::new_ori(
+                    DeltaDims::arm_dir(i) * dims.base_radius,
+                    Mat3::from_rotation_z(DeltaDims::arm_angle(i))
+                ))));
+            }
+
+            let effector = dims.forward(&[Phi::ZERO; 3]).unwrap_or(Vec3::ZERO);
+            wobj = wobj.add_point_inline("effector", PointRef::new(Position::from(effector)));
+
+            Self {
+                _kinematic: DeltaKinematic::new(dims, [
+                    KinElement::new(Movement::Rotation(Rot::Y), wobj.point("arm_0").unwrap()),
+                    KinElement::new(Movement::Rotation(Rot::Y), wobj.point("arm_1").unwrap()),
+                    KinElement::new(Movement::Rotation(Rot::Y), wobj.point("arm_2").unwrap())
+                ], wobj.point("effector").unwrap()),
+                _world_obj: wobj,
+
+                phi_min: Phi(-PI / 2.0),
+                phi_max: Phi(PI / 2.0),
+
+                __axis_config: ()
+            }
+        }
+
+        /// Sets the limits of the upper arms, the default limits are -90° (upwards) and 90° (downwards)
+        pub fn with_limits(mut self, phi_min : Phi, phi_max : Phi) -> Self {
+            self.phi_min = phi_min;
+            self.phi_max = phi_max;
+            self
+        }
+
+        /// Returns the dimensions of the robot
+        #[inline]
+        pub fn dims(&self) -> &DeltaDims {
+            self._kinematic.dims()
+        }
+
+        /// Checks if the given effector position (without the tool) lies within the workspace of the robot, returns 
+        /// the `Phi` values of the upper arms if it does
+        pub fn check_workspace(&self, pos : Vec3) -> Result<[Phi; 3], crate::Error> {
+            if pos.z >= 0.0 {
+                return Err(format!("The position {:?} lies above the base of the delta robot", pos).into());
+            }
+
+            let phis = self.dims().inverse(pos)?;
+
+            for (i, phi) in phis.iter().enumerate() {
+                if (*phi < self.phi_min) || (*phi > self.phi_max) {
+                    return Err(format!("The position {:?} requires arm {} to exceed its limits ({:?} not in {:?} - {:?})", 
+                        pos, i, phi, self.phi_min, self.phi_max).into());
+                }
+            }
+
+            Ok(phis)
+        }
+
+        /// Returns wheiter the given position lies within the workspace of the robot
+        #[inline]
+        pub fn in_workspace(&self, pos : Vec3) -> bool {
+            self.check_workspace(pos).is_ok()
+        }
+    }
+
+    impl Descriptor<3> for DeltaDescriptor {
+        // Types
+            type AxisConfig = ();
+            type Kinematic = DeltaKinematic;
+        // 
+
+        descriptor_common!(3, __axis_config);
+
+        // Calculation
+            fn phis_for_pos(&self, pos : Position) -> Result<[Phi; 3], crate::Error> {
+                self.check_workspace(*pos.pos() - *self._kinematic.tool().pos())
+            }
+        //
+    }
+// 
@@ -0,0 +1,212 @@
+use glam::Quat;
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+use crate::Descriptor;
+use crate::desc::{Kinematic, SerialKinematic};
+use crate::rcs::{Point, Position, WorldObj};
+use crate::rcs::math::solve_linear;
+
+/// Step used for the numeric derivatives of the inverse kinematics
+const IK_STEP : f32 = 1e-3;
+
+// Options
+    /// Options of the numeric inverse kinematics of a `SerialDescriptor`
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct IkOptions {
+        /// Maximum number of iterations per start configuration
+        pub max_iterations : usize,
+        /// Maximum distance between the reached and the requested position
+        pub tolerance : f32,
+        /// Maximum angle (in radians) between the reached and the requested orientation, if the orientation is solved
+        pub ori_tolerance : f32,
+        /// Whether or not the orientation of the requested pose is solved as well, kinematics with less than six
+        /// axes can usually only reach the position
+        pub solve_ori : bool,
+        /// Weight of orientation errors compared to position errors (length per radian)
+        pub ori_weight : f32
+    }
+
+    impl Default for IkOptions {
+        fn default() -> Self {
+            Self {
+                max_iterations: 200,
+                tolerance: 0.01,
+                ori_tolerance: 1e-3,
+                solve_ori: false,
+                ori_weight: 100.0
+            }
+        }
+    }
+//
+
+// Descriptor
+    /// A descriptor for arbitrary serial kinematics, the inverse kinematics is solved numerically (damped least
+    /// squares), starting at the current joint values of the kinematic
+    pub struct SerialDescriptor<const C : usize> {
+        _kinematic : SerialKinematic<C>,
+        _world_obj : WorldObj,
+
+        /// Options of the inverse kinematics
+        pub options : IkOptions,
+        /// Lower limits of the joint values
+        pub limits_min : [Option<Phi>; C],
+        /// Upper limits of the joint values
+        pub limits_max : [Option<Phi>; C],
+
+        __axis_config : ()
+    }
+
+    impl<const C : usize> SerialDescriptor<C> {
+        /// Creates a new descriptor out of a kinematic and the tree it is based on, the orientation is solved for
+        /// kinematics with six or more axes
+        pub fn new(world_obj : WorldObj, kinematic : SerialKinematic<C>) -> Self {
+            Self {
+                _kinematic: kinematic,
+                _world_obj: world_obj,
+
+                options: IkOptions { solve_ori: C >= 6, ..Default::default() },
+                limits_min: [None; C],
+                limits_max: [None; C],
+
+                __axis_config: ()
+            }
+        }
+
+        /// Sets the limits of the joint values, the inverse kinematics only returns values within the limits
+        pub fn with_limits(mut self, min : [Option<Phi>; C], max : [Option<Phi>; C]) -> Self {
+            self.limits_min = min;
+            self.limits_max = max;
+            self
+        }
+
+        fn clamp(&self, phis : &mut [Phi; C]) {
+            for (i, phi) in phis.iter_mut().enumerate() {
+                if let Some(min) = self.limits_min[i] {
+                    phi.0 = phi.0.max(min.0);
+                }
+
+                if let Some(max) = self.limits_max[i] {
+                    phi.0 = phi.0.min(max.0);
+                }
+            }
+        }
+
+        /// The remaining error between the target and the given end pose, position first and weighted orientation
+        /// second (if solved)
+        fn error(&self, target : &Position, end : &Position) -> Vec<f32> {
+            let mut err = (*target.pos() - *end.pos()).to_array().to_vec();
+
+            if self.options.solve_ori {
+                let (axis, angle) = Quat::from_mat3(&(*target.ori() * end.ori().transpose())).normalize().to_axis_angle();
+                err.extend_from_slice(&(axis * angle * self.options.ori_weight).to_array());
+            }
+
+            err
+        }
+
+        fn converged(&self, err : &[f32]) -> bool {
+            let pos_err = (err[0].powi(2) + err[1].powi(2) + err[2].powi(2)).sqrt();
+            let ori_err = err[3 ..].iter().map(|e| e.powi(2)).sum::<f32>().sqrt() / self.options.ori_weight;
+
+            (pos_err <= self.options.tolerance) && (ori_err <= self.options.ori_tolerance)
+        }
+
+        /// Solves the inverse kinematics for the given TCP pose, starting at the given joint values
+        pub fn solve(&self, target : &Position, start : [Phi; C]) -> Option<[Phi; C]> {
+            let mut phis = start;
+            self.clamp(&mut phis);
+
+            let mut err = self.error(target, &self._kinematic.calculate_end_for(&phis));
+
+            for _ in 0 .. self.options.max_iterations {
+                if self.converged(&err) {
+                    return Some(phis);
+                }
+
+                // Central differences, the error decreases when the end moves towards the target
+                let mut jac = vec![ vec![ 0.0f64; C ]; err.len() ];
+
+                for j in 0 .. C {
+                    let mut plus = phis;
+                    let mut minus = phis;
+                    plus[j].0 += IK_STEP;
+                    minus[j].0 -= IK_STEP;
+
+                    let err_p = self.error(target, &self._kinematic.calculate_end_for(&plus));
+                    let err_m = self.error(target, &self._kinematic.calculate_end_for(&minus));
+
+                    for (row, (p, m)) in jac.iter_mut().zip(err_p.iter().zip(&err_m)) {
+                        row[j] = ((m - p) / 2.0 / IK_STEP) as f64;
+                    }
+                }
+
+                // Damped least squares: dq = J^T (J J^T + lambda^2 I)^-1 e
+                let mut a : Vec<Vec<f64>> = jac.iter()
+                    .map(|ri| jac.iter().map(|rk| ri.iter().zip(rk).map(|(x, y)| x * y).sum()).collect())
+                    .collect();
+
+                let trace : f64 = (0 .. a.len()).map(|i| a[i][i]).sum();
+                let damping = 1e-6 * trace / a.len() as f64 + 1e-9;
+
+                for (i, row) in a.iter_mut().enumerate() {
+                    row[i] += damping;
+                }
+
+                let y = solve_linear(a, err.iter().map(|e| *e as f64).collect())?;
+
+                let mut step = [ 0.0f32; C ];
+                for (j, s) in step.iter_mut().enumerate() {
+                    *s = jac.iter().zip(&y).map(|(row, y)| row[j] * y).sum::<f64>() as f32;
+                }
+
+                // Backtracking, the step is halved until the error decreases
+                let norm = |err : &[f32]| err.iter().map(|e| e.powi(2)).sum::<f32>();
+                let mut alpha = 1.0;
+
+                loop {
+                    let mut next = phis;
+                    for (phi, s) in next.iter_mut().zip(step) {
+                        phi.0 += s * alpha;
+                    }
+                    self.clamp(&mut next);
+
+                    let next_err = self.error(target, &self._kinematic.calculate_end_for(&next));
+
+                    if norm(&next_err) < norm(&err) {
+                        phis = next;
+                        err = next_err;
+                        break;
+                    }
+
+                    alpha /= 2.0;
+
+                    // No further improvement possible (local minimum or limits reached)
+                    if alpha < 1e-4 {
+                        return None;
+                    }
+                }
+            }
+
+            if self.converged(&err) { Some(phis) } else { None }
+        }
+    }
+
+    impl<const C : usize> Descriptor<C> for SerialDescriptor<C> {
+        // Types
+            type AxisConfig = ();
+            type Kinematic = SerialKinematic<C>;
+        //
+
+        descriptor_common!(C, __axis_config);
+
+        // Calculation
+            fn phis_for_pos(&self, pos : Position) -> Result<[Phi; C], crate::Error> {
+                // Start at the current joint values, fall back to the zero position
+                self.solve(&pos, self._kinematic.phis())
+                    .or_else(|| self.solve(&pos, [Phi::ZERO; C]))
+                    .ok_or_else(|| format!("The position {:?} is out of reach", pos).into())
+            }
+        //
+    }
+//
@@ -0,0 +1,249 @@
+use core::f32::consts::PI;
+
+use glam::{Mat3, Vec3};
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+use crate::Descriptor;
+use crate::config::{nearest_phis, ArmConfig, AxisConfig, BranchSelection, Elbow, Shoulder, Wrist};
+use crate::desc::{KinElement, Kinematic, Movement, Rot, SerialKinematic};
+use crate::rcs::{Point, PointRef, Position, WorldObj};
+use crate::rcs::math::normalize_angle;
+
+// Dimensions
+    /// The dimensions of a six-axis articulated arm with a spherical wrist
+    /// 
+    /// In the zero position the upper arm points upwards (Z-direction) and the forearm and flange point into the 
+    /// X-direction, the orientation of the flange is then equal to the orientation of the base
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct SixAxisDims {
+        /// Height of the shoulder joint above the base
+        pub base_height : f32,
+        /// Horizontal offset of the shoulder joint from the first axis
+        pub shoulder_offset : f32,
+        /// Length of the upper arm (shoulder to elbow)
+        pub upper_arm : f32,
+        /// Offset of the forearm axis from the elbow, perpendicular to the forearm
+        pub elbow_offset : f32,
+        /// Length of the forearm (elbow to wrist center)
+        pub forearm : f32,
+        /// Distance from the wrist center to the flange
+        pub wrist_length : f32
+    }
+
+    /// A single solution of the inverse kinematics of a six-axis arm
+    #[derive(Clone, Copy, Debug)]
+    pub struct ArmSolution {
+        /// The `Phi` values of all joints
+        pub phis : [Phi; 6],
+        /// The shoulder configuration of the solution
+        pub shoulder : Shoulder,
+        /// The elbow configuration of the solution
+        pub elbow : Elbow,
+        /// The wrist configuration of the solution
+        pub wrist : Wrist
+    }
+
+    impl ArmSolution {
+        /// Checks wheiter the solution matches the flags of the given `ArmConfig`
+        pub fn matches(&self, config : &ArmConfig) -> bool {
+            (self.shoulder == config.shoulder) && (self.elbow == config.elbow) && (self.wrist == config.wrist)
+        }
+    }
+
+    #[inline]
+    fn el(m : &Mat3, row : usize, col : usize) -> f32 {
+        m.col(col)[row]
+    }
+
+    impl SixAxisDims {
+        /// Calculates all solutions of the inverse kinematics for the given flange position, an empty vector is returned 
+        /// if the position cannot be reached
+        /// 
+        /// The position and orientation are decoupled by the spherical wrist, the first three joints move the wrist center,
+        /// the last three joints the orientation
+        pub fn solutions(&self, pos : &Position) -> Vec<ArmSolution> {
+            let mut solutions = Vec::new();
+            let wrist = *pos.pos() - *pos.ori() * Vec3::X * self.wrist_length;
+
+            // Elbow triangle
+            let forearm_len = (self.forearm.powi(2) + self.elbow_offset.powi(2)).sqrt();
+            let forearm_angle = self.elbow_offset.atan2(self.forearm);
+
+            let dist_xy = (wrist.x.powi(2) + wrist.y.powi(2)).sqrt();
+            let base_angle = wrist.y.atan2(wrist.x);
+
+            for shoulder in [ Shoulder::Front, Shoulder::Back ] {
+                let (phi_1, radial) = match shoulder {
+                    Shoulder::Front => (base_angle, dist_xy),
+                    Shoulder::Back => (normalize_angle(base_angle + PI), -dist_xy)
+                };
+
+                // Wrist center relative to the shoulder in the arm plane
+                let x = radial - self.shoulder_offset;
+                let z = wrist.z - self.base_height;
+
+                let cos_elbow = (x.powi(2) + z.powi(2) - self.upper_arm.powi(2) - forearm_len.powi(2)) 
+                    / 2.0 / self.upper_arm / forearm_len;
+
+                if cos_elbow.abs() > 1.0 {
+                    continue;
+                }
+
+                for elbow_sign in [ -1.0, 1.0 ] {
+                    let elbow_angle = elbow_sign * cos_elbow.acos();
+
+                    let upper_angle = z.atan2(x) - (forearm_len * elbow_angle.sin()).atan2(self.upper_arm + forearm_len * elbow_angle.cos());
+                    let phi_2 = normalize_angle(PI / 2.0 - upper_angle);
+                    let phi_3 = normalize_angle(forearm_angle - PI / 2.0 - elbow_angle);
+
+                    let elbow = if (elbow_angle < 0.0) == (shoulder == Shoulder::Front) {
+                        Elbow::Up
+                    } else {
+                        Elbow::Down
+                    };
+
+                    // Remaining rotation of the wrist, decomposed into X-Y-X euler angles
+                    let ori_3 = Mat3::from_rotation_z(phi_1) * Mat3::from_rotation_y(phi_2 + phi_3);
+                    let ori_w = ori_3.transpose() * *pos.ori();
+
+                    let phi_5 = el(&ori_w, 0, 0).clamp(-1.0, 1.0).acos();
+                    let (phi_4, phi_6) = if phi_5.sin().abs() > 1e-5 {
+                        (
+                            el(&ori_w, 1, 0).atan2(-el(&ori_w, 2, 0)),
+                            el(&ori_w, 0, 1).atan2(el(&ori_w, 0, 2))
+                        )
+                    } else if el(&ori_w, 0, 0) > 0.0 {
+                        // Singularity, the roll axes are aligned
+                        (0.0, el(&ori_w, 2, 1).atan2(el(&ori_w, 1, 1)))
+                    } else {
+                        (0.0, -el(&ori_w, 2, 1).atan2(el(&ori_w, 1, 1)))
+                    };
+
+                    for wrist in [ Wrist::NoFlip, Wrist::Flip ] {
+                        let (phi_4, phi_5, phi_6) = match wrist {
+                            Wrist::NoFlip => (phi_4, phi_5, phi_6),
+                            Wrist::Flip => (normalize_angle(phi_4 + PI), -phi_5, normalize_angle(phi_6 + PI))
+                        };
+
+                        solutions.push(ArmSolution {
+                            phis: [ Phi(phi_1), Phi(phi_2), Phi(phi_3), Phi(phi_4), Phi(phi_5), Phi(phi_6) ],
+                            shoulder, 
+                            elbow,
+                            wrist
+                        });
+                    }
+                }
+            }
+
+            solutions
+        }
+    }
+// 
+
+// Descriptor
+    /// A six-axis articulated arm with a spherical wrist
+    /// 
+    /// Joint 1 rotates around the Z-axis, joint 2 and 3 around the Y-axis, joint 4 around the forearm (X), joint 5 around
+    /// the Y-axis and joint 6 around the flange axis (X)
+    pub struct SixAxisDescriptor {
+        _kinematic : SerialKinematic<6>,
+        _world_obj : WorldObj,
+
+        dims : SixAxisDims,
+
+        _axis_config : ArmConfig
+    }
+
+    impl SixAxisDescriptor {
+        /// Creates a new six-axis descriptor out of the given dimensions
+        pub fn new(dims : SixAxisDims) -> Self {
+            let wobj = WorldObj::zero()
+                .add_point_inline("base", PointRef::new(
+                    WorldObj::zero()
+                        .add_point_inline("shoulder", PointRef::new(
+                            WorldObj::new(dims.shoulder_offset, 0.0, dims.base_height)
+                                .add_point_inline("elbow", PointRef::new(
+                                    WorldObj::new(0.0, 0.0, dims.upper_arm)
+                                        .add_point_inline("forearm", PointRef::new(
+                                            WorldObj::new(dims.forearm, 0.0, dims.elbow_offset)
+                                                .add_point_inline("wrist", PointRef::new(
+                                                    WorldObj::zero()
+                                                        .add_point_inline("flange", PointRef::new(
+                                                            Position::new(dims.wrist_length, 0.0, 0.0)
+                                                        ))
+                                                ))
+                                        ))
+                                ))
+                        ))
+                ));
+
+            Self {
+                _kinematic: SerialKinematic::with_tcp([
+                    KinElement::new(Movement::Rotation(Rot::Z), wobj.point("base").unwrap()),
+                    KinElement::new(Movement::Rotation(Rot::Y), wobj.point("base/shoulder").unwrap()),
+                    KinElement::new(Movement::Rotation(Rot::Y), wobj.point("base/shoulder/elbow").unwrap()),
+                    KinElement::new(Movement::Rotation(Rot::X), wobj.point("base/shoulder/elbow/forearm").unwrap()),
+                    KinElement::new(Movement::Rotation(Rot::Y), wobj.point("base/shoulder/elbow/forearm/wrist").unwrap()),
+                    KinElement::new(Movement::Rotation(Rot::X), wobj.point("base/shoulder/elbow/forearm/wrist/flange").unwrap())
+                ], Position::zero()),
+                _world_obj: wobj,
+
+                dims,
+
+                _axis_config: ArmConfig::default()
+            }
+        }
+
+        /// Returns the dimensions of the arm
+        #[inline]
+        pub fn dims(&self) -> &SixAxisDims {
+            &self.dims
+        }
+
+        /// Returns all solutions of the inverse kinematics for the given TCP position (including the tool), 
+        /// see `SixAxisDims::solutions()`
+        #[inline]
+        pub fn solutions(&self, pos : &Position) -> Vec<ArmSolution> {
+            self.dims.solutions(&self._kinematic.end_for_tcp(pos))
+        }
+    }
+
+    impl Descriptor<6> for SixAxisDescriptor {
+        // Types
+            type AxisConfig = ArmConfig;
+            type Kinematic = SerialKinematic<6>;
+        // 
+
+        descriptor_common!(6, _axis_config);
+
+        // Calculation
+            fn phis_for_pos(&self, pos : Position) -> Result<[Phi; 6], crate::Error> {
+                let solutions = self.dims.solutions(&self._kinematic.end_for_tcp(&pos));
+
+                if solutions.is_empty() {
+                    return Err(format!("The position {:?} is out of reach", pos).into());
+                }
+
+                match self._axis_config.selection {
+                    BranchSelection::Flags => {
+                        let mut phis = solutions.iter()
+                            .find(|sol| sol.matches(&self._axis_config))
+                            .map(|sol| sol.phis)
+                            .ok_or_else(|| format!("The position {:?} cannot be reached with the configuration {:?}", pos, self._axis_config))?;
+
+                        self._axis_config.apply_turns(&mut phis);
+                        Ok(phis)
+                    },
+                    BranchSelection::Nearest => {
+                        let reference = <[Phi; 6]>::try_from(self._axis_config.phis())
+                            .unwrap_or_else(|_| self._kinematic.phis());
+
+                        nearest_phis(solutions.iter().map(|sol| sol.phis), &reference)
+                            .ok_or_else(|| format!("The position {:?} is out of reach", pos).into())
+                    }
+                }
+            }
+        //
+    }
+// 
@@ -0,0 +1,152 @@
+use glam::{Mat3, Vec3};
+use serde::{Serialize, Deserialize};
+
+use crate::desc::{KinElement, Movement, Rot, SerialKinematic};
+use crate::rcs::{Point, PointRef, Position, WorldObj};
+use crate::rcs::math::chain_poses;
+
+/// The convention used to interpret the parameters of a `DhTable`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DhConvention {
+    /// Standard (distal) convention, a joint transform is `Rot_z(theta) * Trans_z(d) * Trans_x(a) * Rot_x(alpha)`
+    #[default]
+    Standard,
+    /// Modified (proximal, Craig) convention, a joint transform is `Rot_x(alpha) * Trans_x(a) * Rot_z(theta) * Trans_z(d)`
+    Modified
+}
+
+/// The type of joint described by a set of DH-parameters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DhJoint {
+    /// The joint rotates around the Z-axis, the `Phi` value is added to `theta`
+    #[default]
+    Revolute,
+    /// The joint moves along the Z-axis, the `Phi` value is added to `d`
+    Prismatic
+}
+
+/// Denavit-Hartenberg parameters of a single joint
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DhParam {
+    /// Link length (translation along the X-axis)
+    #[serde(default)]
+    pub a : f32,
+    /// Link twist (rotation around the X-axis)
+    #[serde(default)]
+    pub alpha : f32,
+    /// Link offset (translation along the Z-axis)
+    #[serde(default)]
+    pub d : f32,
+    /// Joint angle offset (rotation around the Z-axis)
+    #[serde(default)]
+    pub theta : f32,
+    /// Type of the joint
+    #[serde(default)]
+    pub joint : DhJoint
+}
+
+impl DhParam {
+    /// Creates a new set of parameters for a revolute joint
+    pub fn revolute(a : f32, alpha : f32, d : f32, theta : f32) -> Self {
+        Self { a, alpha, d, theta, joint: DhJoint::Revolute }
+    }
+
+    /// Creates a new set of parameters for a prismatic joint
+    pub fn prismatic(a : f32, alpha : f32, d : f32, theta : f32) -> Self {
+        Self { a, alpha, d, theta, joint: DhJoint::Prismatic }
+    }
+}
+
+/// A table of Denavit-Hartenberg parameters describing a serial kinematic chain
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DhTable {
+    /// Convention used to interpret the parameters
+    #[serde(default)]
+    pub convention : DhConvention,
+    /// Parameters of all joints, starting at the base
+    pub joints : Vec<DhParam>,
+    /// Offset of the TCP relative to the frame of the last joint
+    #[serde(default)]
+    pub tcp : Option<Position>
+}
+
+impl DhTable {
+    /// Creates a new DH-table with the given convention and joints
+    pub fn new(convention : DhConvention, joints : Vec<DhParam>) -> Self {
+        Self { convention, joints, tcp: None }
+    }
+
+    /// Sets the offset of the TCP relative to the last joint frame
+    pub fn with_tcp(mut self, tcp : Position) -> Self {
+        self.tcp = Some(tcp);
+        self
+    }
+}
+
+impl<const C : usize> SerialKinematic<C> {
+    /// Builds a serial kinematic out of a DH-table, returning the kinematic and the `WorldObj` hierarchy it is based on
+    /// 
+    /// The joints are stored as nested points `joint_0/joint_1/...`, the last joint contains the point `tcp`
+    pub fn from_dh(table : &DhTable) -> Result<(Self, WorldObj), crate::Error> {
+        if table.joints.len() != C {
+            return Err(format!("The DH-table contains {} joints, but the kinematic requires {}", table.joints.len(), C).into());
+        }
+
+        // Static transforms in front of each joint and behind the last one
+        let mut pre = Vec::with_capacity(C);
+        let mut post = Position::zero();
+
+        for param in &table.joints {
+            match table.convention {
+                DhConvention::Standard => {
+                    pre.push(post);
+                    post = Position::new_ori(
+                        Mat3::from_rotation_z(param.theta) * Vec3::new(param.a, 0.0, param.d),
+                        Mat3::from_rotation_z(param.theta) * Mat3::from_rotation_x(param.alpha)
+                    );
+                },
+                DhConvention::Modified => {
+                    pre.push(chain_poses(&post, &Position::new_ori(Vec3::new(param.a, 0.0, 0.0), Mat3::from_rotation_x(param.alpha))));
+                    post = Position::new_ori(Vec3::new(0.0, 0.0, param.d), Mat3::from_rotation_z(param.theta));
+                }
+            }
+        }
+
+        if let Some(tcp) = &table.tcp {
+            post = chain_poses(&post, tcp);
+        }
+
+        // Build the hierarchy from the inside out
+        let mut point = PointRef::new(post.clone());
+        let mut name = String::from("tcp");
+
+        for i in (0 .. C).rev() {
+            point = PointRef::new(WorldObj::from_pos(pre[i].clone()).add_point_inline(name, point));
+            name = format!("joint_{}", i);
+        }
+
+        let wobj = WorldObj::zero().add_point_inline(name, point);
+
+        let mut segments = Vec::with_capacity(C);
+        let mut path = String::new();
+
+        for (i, param) in table.joints.iter().enumerate() {
+            if i > 0 {
+                path.push('/');
+            }
+            path.push_str(&format!("joint_{}", i));
+
+            let movement = match param.joint {
+                DhJoint::Revolute => Movement::Rotation(Rot::Z),
+                DhJoint::Prismatic => Movement::Linear(*pre[i].ori() * Vec3::Z)
+            };
+
+            segments.push(KinElement::new(movement, wobj.req_point(path.clone())?));
+        }
+
+        let segments : [KinElement; C] = segments.try_into()
+            .map_err(|_| "Bad number of segments generated")?;
+
+        Ok((Self::with_tcp(segments, post), wobj))
+    }
+}
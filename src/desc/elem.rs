@@ -1,8 +1,11 @@
+use core::f32::consts::PI;
+
 use glam::{Vec3, Mat3};
 use syunit::*;
 
-use crate::rcs::PointRef;
+use crate::rcs::{Point, PointRef, Position};
 
+/// The basic axes of rotation
 #[derive(Debug, Clone)]
 pub enum Rot {
     X,
@@ -10,10 +13,43 @@ pub enum Rot {
     Z
 }
 
+/// The movement of a `KinElement`
 #[derive(Debug)]
 pub enum Movement {
+    /// Rotation around one of the basic axes
     Rotation(Rot),
-    Linear(Vec3)
+    /// Linear movement along the given vector (in the coordinates of the parent system), scaled by the `Phi` value
+    Linear(Vec3),
+    /// Rotation around an arbitrary `axis` passing through the point `pivot`, both given in the coordinates of the 
+    /// elements base point
+    Revolute { 
+        /// The axis of rotation
+        axis : Vec3, 
+        /// A point on the axis of rotation
+        pivot : Vec3 
+    },
+    /// Helical movement, rotation around an arbitrary `axis` passing through the point `pivot` combined with a 
+    /// translation of `lead` along the axis per full revolution
+    Screw { 
+        /// The axis of rotation and translation
+        axis : Vec3, 
+        /// A point on the axis of rotation
+        pivot : Vec3, 
+        /// The translation per full revolution
+        lead : f32 
+    }
+}
+
+impl Movement {
+    /// Creates a new rotation around the given axis through the given pivot
+    pub fn revolute(axis : Vec3, pivot : Vec3) -> Self {
+        Self::Revolute { axis: axis.normalize(), pivot }
+    }
+
+    /// Creates a new helical movement around the given axis through the given pivot
+    pub fn screw(axis : Vec3, pivot : Vec3, lead : f32) -> Self {
+        Self::Screw { axis: axis.normalize(), pivot, lead }
+    }
 }
 
 #[derive(Debug)]
@@ -44,6 +80,11 @@ impl KinElement {
         pub fn point(&self) -> &PointRef {
             &self._point
         }
+
+        /// The base point of the element (its pose for `Phi::ZERO`), shared with the `WorldObj` tree
+        pub fn base(&self) -> &PointRef {
+            &self.point_0
+        }
     // 
 
     #[inline]
@@ -51,30 +92,50 @@ impl KinElement {
         self._point.pos()    
     }
 
-    pub fn update(&mut self, phi : Phi) -> Result<(), crate::Error> {
-        self._phi = phi;
+    /// Returns the pose of the element for the given `Phi` value, without changing the element
+    pub fn pose_for(&self, phi : Phi) -> Position {
+        let p0 = self.point_0.borrow();
 
         match &self.movement {
-            Movement::Rotation(rot) => {
-                let mut p_ref = self._point.borrow_mut();
-                let p0_ref = self.point_0.borrow();
-
-                match rot {
-                    Rot::X =>
-                        *p_ref.ori_mut() = *p0_ref.ori() * Mat3::from_rotation_x(phi.0),
-                    Rot::Y =>
-                        *p_ref.ori_mut() = *p0_ref.ori() * Mat3::from_rotation_y(phi.0), 
-                    Rot::Z => 
-                        *p_ref.ori_mut() = *p0_ref.ori() * Mat3::from_rotation_z(phi.0)
+            Movement::Rotation(rot) => Position::new_ori(*p0.pos(), *p0.ori() * match rot {
+                Rot::X => Mat3::from_rotation_x(phi.0),
+                Rot::Y => Mat3::from_rotation_y(phi.0),
+                Rot::Z => Mat3::from_rotation_z(phi.0)
+            }),
+            Movement::Linear(pos) => Position::new_ori(*p0.pos() + *pos * phi.0, *p0.ori()),
+            Movement::Revolute { axis, pivot } | Movement::Screw { axis, pivot, .. } => {
+                let axis = axis.normalize();
+                let rot = Mat3::from_axis_angle(axis, phi.0);
+
+                let mut shift = *pivot - rot * *pivot;
+
+                if let Movement::Screw { lead, .. } = &self.movement {
+                    shift += axis * *lead * phi.0 / 2.0 / PI;
                 }
-            },
-            Movement::Linear(pos) => {
-                let mut p_ref = self._point.borrow_mut();
-                let p0_ref = self.point_0.borrow();
 
-                *p_ref.pos_mut() = *p0_ref.pos() + *pos * phi.0;
+                Position::new_ori(*p0.pos() + *p0.ori() * shift, *p0.ori() * rot)
             }
         }
+    }
+
+    /// Moves the element to the given `Phi` value. The observers of the base point in the `WorldObj` tree are notified
+    /// about the moved pose as well, while the tree itself keeps the base pose
+    pub fn update(&mut self, phi : Phi) -> Result<(), crate::Error> {
+        self._phi = phi;
+
+        let pose = self.pose_for(phi);
+        let moved = {
+            let mut p_ref = self._point.borrow_mut();
+            let moved = p_ref.pose() != pose;
+
+            *p_ref.pos_mut() = *pose.pos();
+            *p_ref.ori_mut() = *pose.ori();
+            moved
+        };
+
+        if moved && self.point_0.has_observers() {
+            self.point_0.notify(&pose);
+        }
 
         Ok(())
     }
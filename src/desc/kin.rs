@@ -2,7 +2,7 @@ use core::ops::Index;
 
 use syunit::*;
 
-use crate::rcs::{PointRef, Position, Point};
+use crate::rcs::{PointRef, Position, Point, Transform};
 use crate::desc::KinElement;
 
 pub trait Kinematic<const C : usize> : core::fmt::Debug {
@@ -13,13 +13,28 @@ pub trait Kinematic<const C : usize> : core::fmt::Debug {
         /// All the segments of the kinematic system
         fn segments_mut(&mut self) -> &mut [KinElement; C];
 
-        /// The TCP (Tool-Center-Point) of the kinematic
+        /// The TCP (Tool-Center-Point) of the kinematic, either the point of the last segment (relative to its parent) 
+        /// or a detached point holding the pose of the tool tip in the base coordinates, see `SerialKinematic`
         fn tcp<'a>(&'a self) -> &'a PointRef;
 
         /// The TCP (Tool-Center-Point) of the kinematic
         fn tcp_mut<'a>(&'a mut self) -> &'a mut PointRef;
     // 
 
+    // Tool
+        /// The pose of the equipped tool relative to the flange (the end of the kinematic without a tool), the TCP is 
+        /// placed at the tip of the tool
+        fn tool(&self) -> &Position;
+
+        /// Sets the pose of the equipped tool relative to the flange, the TCP is moved to the tip of the new tool
+        fn set_tool(&mut self, tool : Position);
+
+        /// Returns the pose the flange must reach for the TCP to be at the given pose
+        fn end_for_tcp(&self, tcp : &Position) -> Position {
+            (Transform::from(tcp) * Transform::from(self.tool()).inverse()).to_pos()
+        }
+    // 
+
     // Data
         fn phis<'a>(&'a self) -> [Phi; C] {
             let mut phis = [Phi::ZERO; C];
@@ -30,6 +45,7 @@ pub trait Kinematic<const C : usize> : core::fmt::Debug {
         }
     // 
     
+    /// Calculates the pose of the TCP (including the tool) out of the current state of the segments
     fn calculate_end(&self) -> Position;
 
     // Events
@@ -48,17 +64,92 @@ pub trait Kinematic<const C : usize> : core::fmt::Debug {
 #[derive(Debug)]
 pub struct SerialKinematic<const C : usize> {
     segments : [KinElement; C],
-    tcp : PointRef
+    tcp_offset : Position,
+    tool : Position,
+    tcp : PointRef,
+    /// Whether or not the TCP is a separate point storing the pose of the tool tip, otherwise it is the point of the 
+    /// last segment
+    detached : bool
 }
 
 impl<const C : usize> SerialKinematic<C> {
+    /// Creates a new serial kinematic out of the given segments, the TCP is the point of the last segment (relative to
+    /// its parent, carrying all points attached to the segment). Use `with_tcp()` for a TCP in base coordinates that 
+    /// includes an offset and the equipped tool
+    #[inline]
     pub fn new(segments : [KinElement; C]) -> Self {
-        let last = &segments[C - 1];
+        let tcp = segments[C - 1].point().clone();
 
         Self {
-            tcp: last.point().clone(),
-            segments
+            segments,
+            tcp_offset: Position::zero(),
+            tool: Position::zero(),
+            tcp,
+            detached: false
+        }
+    }
+
+    /// Creates a new serial kinematic out of the given segments with a detached TCP, placed at `tcp_offset` relative to
+    /// the last segment. The TCP stores the pose of the tool tip in the base coordinates and is refreshed with every
+    /// call to `update()`
+    pub fn with_tcp(segments : [KinElement; C], tcp_offset : Position) -> Self {
+        let kin = Self {
+            segments,
+            tcp_offset,
+            tool: Position::zero(),
+            tcp: PointRef::new(Position::zero()),
+            detached: true
+        };
+
+        kin.refresh_tcp();
+        kin
+    }
+
+    /// The offset of the TCP relative to the last segment
+    #[inline]
+    pub fn tcp_offset(&self) -> &Position {
+        &self.tcp_offset
+    }
+
+    /// Returns `true` if the TCP is a detached point (see `with_tcp()`), `false` if it is the point of the last segment
+    #[inline]
+    pub fn is_tcp_detached(&self) -> bool {
+        self.detached
+    }
+
+    /// Calculates the pose of the TCP (including the tool) for the given `Phi` values, without updating the segments
+    pub fn calculate_end_for(&self, phis : &[Phi; C]) -> Position {
+        let mut tf = Transform::from(&self.tcp_offset) * Transform::from(&self.tool);
+
+        for (segment, phi) in self.segments.iter().zip(phis).rev() {
+            tf = Transform::from(&segment.pose_for(*phi)) * tf;
+        }
+
+        tf.to_pos()
+    }
+
+    /// Sets the offset of the TCP relative to the last segment, a TCP shared with the last segment is replaced by a 
+    /// detached one
+    pub fn set_tcp_offset(&mut self, tcp_offset : Position) {
+        if !self.detached {
+            self.tcp = PointRef::new(Position::zero());
+            self.detached = true;
         }
+
+        self.tcp_offset = tcp_offset;
+        self.refresh_tcp();
+    }
+
+    fn refresh_tcp(&self) {
+        if !self.detached {
+            return;
+        }
+
+        let end = self.calculate_end();
+        let mut tcp = self.tcp.borrow_mut();
+
+        *tcp.pos_mut() = *end.pos();
+        *tcp.ori_mut() = *end.ori();
     }
 }
 
@@ -91,18 +182,35 @@ impl<const C : usize> Kinematic<C> for SerialKinematic<C> {
         }
     // 
 
+    // Tool
+        fn tool(&self) -> &Position {
+            &self.tool
+        }
+
+        fn set_tool(&mut self, tool : Position) {
+            self.tool = tool;
+            self.refresh_tcp();
+        }
+    // 
+
     fn calculate_end(&self) -> Position {
-        let segments = self.segments(); 
-        let mut pos_0 = Position::from(self.tcp().borrow().pos().clone());
+        let mut tf = Transform::from(&self.tcp_offset) * Transform::from(&self.tool);
 
-        for i in 1 ..= C {
-            let index = C - i;
-            let point = segments[index].point().borrow();
-    
-            pos_0.transform(*point.ori());
-            pos_0.shift(*point.pos());
+        for segment in self.segments().iter().rev() {
+            tf = Transform::from_pose(&*segment.point().borrow()) * tf;
         }
 
-        pos_0
+        tf.to_pos()
     }
+
+    // Events
+        fn update(&mut self, phis : &[Phi; C]) -> Result<(), crate::Error> {
+            for (segment, phi) in self.segments.iter_mut().zip(phis) {
+                segment.update(*phi)?;
+            }
+
+            self.refresh_tcp();
+            Ok(())
+        }
+    // 
 }
\ No newline at end of file
@@ -0,0 +1,251 @@
+use core::f32::consts::PI;
+use std::collections::HashMap;
+use std::io::Write;
+
+use glam::Vec3;
+use syunit::*;
+
+use crate::Descriptor;
+use crate::desc::Kinematic;
+use crate::rcs::Point;
+
+/// Number of latitude bins used to discretize the approach directions
+const LAT_BINS : usize = 8;
+/// Number of longitude bins used to discretize the approach directions
+const LON_BINS : usize = 16;
+
+// Options
+    /// Options for the generation of a `ReachMap`
+    #[derive(Clone, Debug)]
+    pub struct ReachOptions<const C : usize> {
+        /// Edge length of a single voxel
+        pub voxel_size : f32,
+        /// Number of samples per joint, the samples are spread evenly between the limits (inclusive)
+        pub samples : [usize; C],
+        /// The approach axis of the TCP (in TCP coordinates), used to measure the dexterity
+        pub approach : Vec3
+    }
+
+    impl<const C : usize> ReachOptions<C> {
+        /// Creates new options with the given voxel size and the same number of samples for every joint, the approach
+        /// axis defaults to the X-axis of the TCP
+        pub fn new(voxel_size : f32, samples : usize) -> Self {
+            Self {
+                voxel_size,
+                samples: [samples; C],
+                approach: Vec3::X
+            }
+        }
+    }
+// 
+
+// Voxel
+    /// A single voxel of a `ReachMap`
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Voxel {
+        /// Number of samples that ended in the voxel
+        pub hits : usize,
+        /// Bitmask of the approach directions reached in the voxel
+        directions : u128
+    }
+
+    impl Voxel {
+        /// Returns the number of distinct approach directions reached in the voxel
+        #[inline]
+        pub fn direction_count(&self) -> u32 {
+            self.directions.count_ones()
+        }
+
+        /// The dexterity of the voxel, the fraction of all approach directions that can be reached (0.0 to 1.0)
+        #[inline]
+        pub fn dexterity(&self) -> f32 {
+            self.direction_count() as f32 / (LAT_BINS * LON_BINS) as f32
+        }
+    }
+
+    fn direction_bin(dir : Vec3) -> usize {
+        let dir = dir.normalize_or_zero();
+        let lat = ((dir.z.clamp(-1.0, 1.0).acos() / PI * LAT_BINS as f32) as usize).min(LAT_BINS - 1);
+        let lon = (((dir.y.atan2(dir.x) + PI) / 2.0 / PI * LON_BINS as f32) as usize).min(LON_BINS - 1);
+
+        lat * LON_BINS + lon
+    }
+// 
+
+// Map
+    /// A voxelized map of the workspace of a robot, storing how often each voxel has been reached and with how many
+    /// approach directions (dexterity)
+    #[derive(Clone, Debug)]
+    pub struct ReachMap {
+        voxel_size : f32,
+        voxels : HashMap<[i32; 3], Voxel>
+    }
+
+    impl ReachMap {
+        /// Creates a new empty map with the given voxel size
+        pub fn new(voxel_size : f32) -> Self {
+            Self { voxel_size, voxels: HashMap::new() }
+        }
+
+        /// Samples the joint space of the given kinematic within the limits `(min, max)` of each joint, the kinematic 
+        /// is reset to its previous `Phi` values afterwards
+        /// 
+        /// Samples that cannot be reached by the kinematic (`update()` fails) are skipped
+        pub fn sample<const C : usize, K : Kinematic<C>>(kinematic : &mut K, limits : &[(Phi, Phi); C], options : &ReachOptions<C>) 
+        -> Result<Self, crate::Error> {
+            if options.voxel_size <= 0.0 {
+                return Err("The voxel size of a reach map must be positive".into());
+            }
+
+            let mut map = Self::new(options.voxel_size);
+            let phis_0 = kinematic.phis();
+
+            let mut index = [0usize; C];
+            let mut phis = [Phi::ZERO; C];
+
+            'sampling: loop {
+                for i in 0 .. C {
+                    let (min, max) = limits[i];
+                    let count = options.samples[i].max(1);
+
+                    phis[i] = if count == 1 {
+                        min
+                    } else {
+                        min + (max - min) * (index[i] as f32 / (count - 1) as f32)
+                    };
+                }
+
+                if kinematic.update(&phis).is_ok() {
+                    let end = kinematic.calculate_end();
+                    map.insert(*end.pos(), *end.ori() * options.approach);
+                }
+
+                // Move on to the next sample
+                for (idx, count) in index.iter_mut().zip(options.samples) {
+                    *idx += 1;
+
+                    if *idx < count.max(1) {
+                        continue 'sampling;
+                    }
+
+                    *idx = 0;
+                }
+
+                break;
+            }
+
+            kinematic.update(&phis_0)?;
+            Ok(map)
+        }
+
+        /// Samples the kinematic of the given descriptor, see `ReachMap::sample()`
+        #[inline]
+        pub fn sample_desc<const C : usize, D : Descriptor<C>>(desc : &mut D, limits : &[(Phi, Phi); C], options : &ReachOptions<C>) 
+        -> Result<Self, crate::Error> {
+            Self::sample(desc.kinematic_mut(), limits, options)
+        }
+
+        /// Adds a reached position and approach direction to the map
+        pub fn insert(&mut self, pos : Vec3, approach : Vec3) {
+            let voxel = self.voxels.entry(self.key(pos)).or_default();
+
+            voxel.hits += 1;
+            voxel.directions |= 1 << direction_bin(approach);
+        }
+
+        // Data
+            /// Edge length of a single voxel
+            #[inline]
+            pub fn voxel_size(&self) -> f32 {
+                self.voxel_size
+            }
+
+            /// Number of voxels reached
+            #[inline]
+            pub fn len(&self) -> usize {
+                self.voxels.len()
+            }
+
+            /// Returns wheiter no voxel has been reached
+            #[inline]
+            pub fn is_empty(&self) -> bool {
+                self.voxels.is_empty()
+            }
+
+            /// Returns the voxel containing the given position, if it has been reached
+            pub fn voxel(&self, pos : Vec3) -> Option<&Voxel> {
+                self.voxels.get(&self.key(pos))
+            }
+
+            /// Returns wheiter the voxel containing the given position has been reached
+            #[inline]
+            pub fn is_reachable(&self, pos : Vec3) -> bool {
+                self.voxel(pos).is_some()
+            }
+
+            /// Iterates over the centers of all voxels reached and the voxels themselves
+            pub fn iter(&self) -> impl Iterator<Item = (Vec3, &Voxel)> + '_ {
+                self.voxels.iter().map(|(key, voxel)| (self.center(key), voxel))
+            }
+
+            fn key(&self, pos : Vec3) -> [i32; 3] {
+                (pos / self.voxel_size).floor().as_ivec3().to_array()
+            }
+
+            fn center(&self, key : &[i32; 3]) -> Vec3 {
+                (Vec3::new(key[0] as f32, key[1] as f32, key[2] as f32) + 0.5) * self.voxel_size
+            }
+
+            fn sorted(&self) -> Vec<(Vec3, &Voxel)> {
+                let mut keys : Vec<&[i32; 3]> = self.voxels.keys().collect();
+                keys.sort();
+                keys.into_iter().map(|key| (self.center(key), &self.voxels[key])).collect()
+            }
+        // 
+
+        // Export
+            /// Writes the map as CSV (`x,y,z,hits,dexterity`), one line per voxel center, the writer is flushed at the end
+            pub fn write_csv<W : Write>(&self, mut writer : W) -> Result<(), crate::Error> {
+                writeln!(writer, "x,y,z,hits,dexterity")?;
+
+                for (pos, voxel) in self.sorted() {
+                    writeln!(writer, "{},{},{},{},{}", pos.x, pos.y, pos.z, voxel.hits, voxel.dexterity())?;
+                }
+
+                writer.flush()?;
+                Ok(())
+            }
+
+            /// Writes the map as ASCII PLY point cloud, one vertex per voxel center with the properties `hits` and
+            /// `dexterity`, the writer is flushed at the end
+            pub fn write_ply<W : Write>(&self, mut writer : W) -> Result<(), crate::Error> {
+                writeln!(writer, "ply")?;
+                writeln!(writer, "format ascii 1.0")?;
+                writeln!(writer, "element vertex {}", self.len())?;
+                writeln!(writer, "property float x")?;
+                writeln!(writer, "property float y")?;
+                writeln!(writer, "property float z")?;
+                writeln!(writer, "property uint hits")?;
+                writeln!(writer, "property float dexterity")?;
+                writeln!(writer, "end_header")?;
+
+                for (pos, voxel) in self.sorted() {
+                    writeln!(writer, "{} {} {} {} {}", pos.x, pos.y, pos.z, voxel.hits, voxel.dexterity())?;
+                }
+
+                writer.flush()?;
+                Ok(())
+            }
+
+            /// Saves the map as CSV file, see `ReachMap::write_csv()`
+            pub fn save_csv(&self, path : &str) -> Result<(), crate::Error> {
+                self.write_csv(std::io::BufWriter::new(std::fs::File::create(path)?))
+            }
+
+            /// Saves the map as PLY file, see `ReachMap::write_ply()`
+            pub fn save_ply(&self, path : &str) -> Result<(), crate::Error> {
+                self.write_ply(std::io::BufWriter::new(std::fs::File::create(path)?))
+            }
+        // 
+    }
+// 
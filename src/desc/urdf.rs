@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use glam::{Mat3, Vec3};
+use syunit::*;
+
+use crate::desc::{Kinematic, KinElement, Movement, SerialKinematic};
+use crate::rcs::{LengthUnit, Point, PointRef, Position, WorldObj};
+use crate::rcs::math::chain_poses;
+
+// Joints
+    /// The types of joints supported by the URDF import
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum UrdfJointType {
+        /// Rotation around the joint axis with limits
+        Revolute,
+        /// Rotation around the joint axis without limits
+        Continuous,
+        /// Translation along the joint axis
+        Prismatic,
+        /// Static offset between two links
+        Fixed
+    }
+
+    impl UrdfJointType {
+        fn parse(name : &str) -> Result<Self, crate::Error> {
+            match name {
+                "revolute" => Ok(Self::Revolute),
+                "continuous" => Ok(Self::Continuous),
+                "prismatic" => Ok(Self::Prismatic),
+                "fixed" => Ok(Self::Fixed),
+                _ => Err(format!("Unsupported URDF joint type '{}'", name).into())
+            }
+        }
+    }
+
+    /// A single joint of an URDF file
+    #[derive(Clone, Debug)]
+    pub struct UrdfJoint {
+        /// Name of the joint
+        pub name : String,
+        /// Type of the joint
+        pub joint_type : UrdfJointType,
+        /// Name of the parent link
+        pub parent : String,
+        /// Name of the child link
+        pub child : String,
+        /// Pose of the joint frame relative to the parent link
+        pub origin : Position,
+        /// Axis of the joint (unit vector in the joint frame)
+        pub axis : Vec3,
+        /// Lower limit of the joint, if defined
+        pub lower : Option<Phi>,
+        /// Upper limit of the joint, if defined
+        pub upper : Option<Phi>
+    }
+
+    fn parse_vec3(node : &roxmltree::Node, attr : &str, default : Vec3) -> Result<Vec3, crate::Error> {
+        let Some(value) = node.attribute(attr) else {
+            return Ok(default);
+        };
+
+        let values = value.split_whitespace()
+            .map(|v| v.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|err| format!("Bad value '{}' for attribute '{}': {}", value, attr, err))?;
+
+        if values.len() != 3 {
+            return Err(format!("Attribute '{}' requires three values, got '{}'", attr, value).into());
+        }
+
+        Ok(Vec3::new(values[0], values[1], values[2]))
+    }
+
+    fn parse_limit(node : &roxmltree::Node, attr : &str) -> Result<Option<Phi>, crate::Error> {
+        node.attribute(attr)
+            .map(|value| value.trim().parse::<f32>().map(Phi)
+                .map_err(|err| format!("Bad value '{}' for limit '{}': {}", value, attr, err).into())
+            ).transpose()
+    }
+
+    impl UrdfJoint {
+        fn parse(node : &roxmltree::Node) -> Result<Self, crate::Error> {
+            let name = node.attribute("name").ok_or("URDF joint without a name")?.to_owned();
+            let joint_type = UrdfJointType::parse(node.attribute("type").ok_or_else(|| format!("URDF joint '{}' without a type", name))?)?;
+
+            let child_link = |tag : &str| -> Result<String, crate::Error> {
+                node.children()
+                    .find(|n| n.has_tag_name(tag))
+                    .and_then(|n| n.attribute("link"))
+                    .map(|l| l.to_owned())
+                    .ok_or_else(|| format!("URDF joint '{}' is missing the '{}' link", name, tag).into())
+            };
+
+            let parent = child_link("parent")?;
+            let child = child_link("child")?;
+
+            let mut origin = Position::zero();
+            let mut axis = Vec3::X;
+            let mut lower = None;
+            let mut upper = None;
+
+            for sub in node.children() {
+                if sub.has_tag_name("origin") {
+                    let xyz = parse_vec3(&sub, "xyz", Vec3::ZERO)?;
+                    let rpy = parse_vec3(&sub, "rpy", Vec3::ZERO)?;
+
+                    origin = Position::new_ori(xyz, 
+                        Mat3::from_rotation_z(rpy.z) * Mat3::from_rotation_y(rpy.y) * Mat3::from_rotation_x(rpy.x));
+                } else if sub.has_tag_name("axis") {
+                    axis = parse_vec3(&sub, "xyz", Vec3::X)?.try_normalize()
+                        .ok_or_else(|| format!("URDF joint '{}' has a zero axis", name))?;
+                } else if sub.has_tag_name("limit") {
+                    lower = parse_limit(&sub, "lower")?;
+                    upper = parse_limit(&sub, "upper")?;
+                }
+            }
+
+            if joint_type == UrdfJointType::Continuous {
+                lower = None;
+                upper = None;
+            }
+
+            Ok(Self { name, joint_type, parent, child, origin, axis, lower, upper })
+        }
+
+        /// Returns wheiter the joint can move
+        #[inline]
+        pub fn is_movable(&self) -> bool {
+            self.joint_type != UrdfJointType::Fixed
+        }
+    }
+// 
+
+// Chain
+    /// A serial kinematic chain imported from an URDF file
+    /// 
+    /// All lengths and limits are taken over from the file unchanged (metres and radians), the `WorldObj` of the chain
+    /// is declared in metres and cannot be added to a millimetre based tree before `convert_unit()` has been called
+    #[derive(Debug)]
+    pub struct UrdfChain<const C : usize> {
+        /// Name of the robot
+        pub name : String,
+        /// The `WorldObj` hierarchy of the chain, one nested point per movable joint, the tip link is the innermost point
+        pub world_obj : WorldObj,
+        /// The kinematic of the chain
+        pub kinematic : SerialKinematic<C>,
+        /// The movable joints of the chain, starting at the root link
+        pub joints : Vec<UrdfJoint>
+    }
+
+    impl<const C : usize> UrdfChain<C> {
+        /// Parses the given URDF string, building the chain from the root link to the link `tip`
+        /// 
+        /// If no tip is given, the tree must not contain any branches
+        pub fn parse(urdf : &str, tip : Option<&str>) -> Result<Self, crate::Error> {
+            let doc = roxmltree::Document::parse(urdf)?;
+            let robot = doc.root_element();
+
+            if !robot.has_tag_name("robot") {
+                return Err("The root element of an URDF file must be 'robot'".into());
+            }
+
+            let name = robot.attribute("name").unwrap_or_default().to_owned();
+
+            let links : Vec<&str> = robot.children()
+                .filter(|n| n.has_tag_name("link"))
+                .filter_map(|n| n.attribute("name"))
+                .collect();
+
+            let joints = robot.children()
+                .filter(|n| n.has_tag_name("joint"))
+                .map(|n| UrdfJoint::parse(&n))
+                .collect::<Result<Vec<UrdfJoint>, _>>()?;
+
+            // Joints by their child link
+            let mut parents : HashMap<&str, &UrdfJoint> = HashMap::new();
+            for joint in &joints {
+                if parents.insert(joint.child.as_str(), joint).is_some() {
+                    return Err(format!("The link '{}' has multiple parent joints", joint.child).into());
+                }
+            }
+
+            let tip = match tip {
+                Some(tip) => {
+                    if !links.contains(&tip) {
+                        return Err(format!("The URDF file does not contain a link named '{}'", tip).into());
+                    }
+                    tip
+                },
+                None => {
+                    let leaves : Vec<&str> = links.iter()
+                        .copied()
+                        .filter(|link| !joints.iter().any(|j| j.parent == *link))
+                        .collect();
+
+                    match leaves.as_slice() {
+                        [ leaf ] => *leaf,
+                        _ => return Err(format!("The URDF tree has multiple leaves ({:?}), a tip link must be given", leaves).into())
+                    }
+                }
+            };
+
+            // Walk from the tip up to the root
+            let mut chain = Vec::new();
+            let mut link = tip;
+
+            while let Some(joint) = parents.get(link) {
+                if chain.len() > joints.len() {
+                    return Err("The URDF file contains a cycle".into());
+                }
+
+                chain.push(*joint);
+                link = joint.parent.as_str();
+            }
+
+            chain.reverse();
+
+            let movable = chain.iter().filter(|j| j.is_movable()).count();
+            if movable != C {
+                return Err(format!("The chain from '{}' to '{}' contains {} movable joints, but the kinematic requires {}", 
+                    link, tip, movable, C).into());
+            }
+
+            // Collect the static transforms in front of each movable joint
+            let mut acc = Position::zero();
+            let mut frames = Vec::with_capacity(C);
+
+            for joint in &chain {
+                acc = chain_poses(&acc, &joint.origin);
+
+                if !joint.is_movable() {
+                    continue;
+                }
+
+                let movement = match joint.joint_type {
+                    UrdfJointType::Prismatic => Movement::Linear(*acc.ori() * joint.axis),
+                    _ => Movement::revolute(joint.axis, Vec3::ZERO)
+                };
+
+                frames.push((joint.name.replace('/', "_"), acc, movement));
+                acc = Position::zero();
+            }
+
+            // Build the hierarchy from the inside out
+            let mut point = PointRef::new(acc.clone());
+            let mut point_name = tip.replace('/', "_");
+
+            for (name, frame, _) in frames.iter().rev() {
+                point = PointRef::new(WorldObj::from_pos(frame.clone()).add_point_inline(point_name, point));
+                point_name = name.clone();
+            }
+
+            let mut world_obj = WorldObj::zero().add_point_inline(point_name, point);
+            world_obj.set_unit(LengthUnit::Metre);
+
+            let mut segments = Vec::with_capacity(C);
+            let mut path = String::new();
+
+            for (name, _, movement) in frames {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(&name);
+
+                segments.push(KinElement::new(movement, world_obj.req_point(path.clone())?));
+            }
+
+            let segments : [KinElement; C] = segments.try_into()
+                .map_err(|_| "Bad number of segments generated")?;
+
+            Ok(Self {
+                name,
+                world_obj,
+                kinematic: SerialKinematic::with_tcp(segments, acc),
+                joints: chain.into_iter().filter(|j| j.is_movable()).cloned().collect()
+            })
+        }
+
+        /// Reads and parses the given URDF file, see `UrdfChain::parse()`
+        pub fn from_file(path : &str, tip : Option<&str>) -> Result<Self, crate::Error> {
+            Self::parse(&std::fs::read_to_string(path)?, tip)
+        }
+
+        /// The unit of all lengths of the chain
+        #[inline]
+        pub fn unit(&self) -> LengthUnit {
+            self.world_obj.unit()
+        }
+
+        /// Converts all lengths of the chain into the given unit, including the offsets and limits of prismatic joints
+        pub fn convert_unit(&mut self, unit : LengthUnit) -> Result<(), crate::Error> {
+            let factor = self.unit().factor_to(unit);
+            let prismatic = |joint : &UrdfJoint| joint.joint_type == UrdfJointType::Prismatic;
+
+            // The segments share their base points with the tree
+            self.world_obj.convert_unit(unit);
+
+            let mut tcp_offset = self.kinematic.tcp_offset().clone();
+            tcp_offset.scale(factor);
+            self.kinematic.set_tcp_offset(tcp_offset);
+
+            let mut phis = self.kinematic.phis();
+
+            for (phi, joint) in phis.iter_mut().zip(self.joints.iter_mut()) {
+                joint.origin.scale(factor);
+
+                if prismatic(joint) {
+                    *phi = *phi * factor;
+                    joint.lower = joint.lower.map(|l| l * factor);
+                    joint.upper = joint.upper.map(|u| u * factor);
+                }
+            }
+
+            self.kinematic.update(&phis)
+        }
+
+        /// Returns the lower and upper limits of all joints
+        pub fn limits(&self) -> ([Option<Phi>; C], [Option<Phi>; C]) {
+            let mut lower = [None; C];
+            let mut upper = [None; C];
+
+            for (i, joint) in self.joints.iter().enumerate() {
+                lower[i] = joint.lower;
+                upper[i] = joint.upper;
+            }
+
+            (lower, upper)
+        }
+    }
+// 
@@ -13,6 +13,7 @@ extern crate alloc;
 // ####################
     /// Configurations for the robot in terms of position, speed and mode
     pub mod config;
+    pub use config::JsonConfig;
 
     /// Quick and easy import of the library essentials
     pub mod prelude; 
@@ -38,6 +39,9 @@ extern crate alloc;
     /// Everything related to the `Station` trait
     pub mod stat;
     pub use stat::Station;
+
+    /// Teaching of positions and frames
+    pub mod teach;
 // 
 
 // ################
@@ -1,21 +1,44 @@
 extern crate alloc;
 
-use core::{cell::RefCell, fmt::Debug, ops::{Deref, DerefMut}};
+use core::f32::consts::PI;
+use core::{fmt::Debug, ops::{Deref, DerefMut}};
 use std::collections::HashMap;
 
-use alloc::rc::Rc;
-use glam::{Vec3, Mat3};
+use alloc::sync::Arc;
+use glam::{Vec3, Mat3, Quat};
 use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 // use serde::de::DeserializeOwned;
 
 // Submodules
+    mod cell;
+    pub use cell::{ObserverId, PointCell, PointGuard, PointReadGuard, PoseChange};
+
     mod des;
 
+    mod frame;
+    pub use frame::FramedPosition;
+
+    mod geom;
+    pub use geom::{Geometry, GeometryUsage, Mesh, Shape};
+
+    mod ori;
+    pub use ori::{EulerOrder, mat3_to_rpy, rpy_to_mat3};
+
     /// Mathematical operations of the coordinate system
     pub mod math;
+
+    mod scene;
+    pub use scene::{SceneFile, SCENE_VERSION};
+
+    mod transform;
+    pub use transform::Transform;
+
+    mod unit;
+    pub use unit::LengthUnit;
 // 
 
-pub trait Point : Debug {
+pub trait Point : Debug + Send + Sync {
     // Coords
         /// X-Coordinate of the point
         fn x(&self) -> f32;
@@ -31,12 +54,37 @@ pub trait Point : Debug {
     fn ori<'a>(&'a self) -> &'a Mat3;
     fn ori_mut<'a>(&'a mut self) -> &'a mut Mat3;
 
+    /// Shifts the point by the given vector (in the coordinates of the parent system)
     fn shift(&mut self, by : Vec3);
+    /// Rotates the point around the origin of the parent system, both position and orientation are rotated
     fn transform(&mut self, by : Mat3);
 
+    /// Applies the given rigid transformation (in the coordinates of the parent system) to the point, both position 
+    /// and orientation are transformed
+    fn apply(&mut self, tf : &Transform) {
+        let pose = tf.apply_pos(&self.pose());
+        *self.pos_mut() = *pose.pos();
+        *self.ori_mut() = *pose.ori();
+    }
+
+    /// Returns a copy of the pose of the point (position and orientation)
+    fn pose(&self) -> Position {
+        Position::new_ori(*self.pos(), *self.ori())
+    }
+
+    /// Scales the position of the point by the given factor, used to convert between length units
+    fn scale(&mut self, factor : f32) {
+        *self.pos_mut() *= factor;
+    }
+
     fn as_pos<'a>(&'a self) -> Option<&'a Position>;
     fn as_wo<'a>(&'a self) -> Option<&'a WorldObj>;
 
+    /// Returns a mutable reference to the point as `WorldObj`, if it is one
+    fn as_wo_mut(&mut self) -> Option<&mut WorldObj> {
+        None
+    }
+
     fn trans_other(&self, v : Vec3) -> Vec3 {
         (*self.ori()) * v 
     }
@@ -51,7 +99,7 @@ pub trait Point : Debug {
 }
 
 // Position
-    #[derive(Clone, Debug)]
+    #[derive(Clone, Debug, PartialEq)]
     pub struct Position {
         pos : Vec3,
         ori : Mat3
@@ -77,6 +125,55 @@ pub trait Point : Debug {
             Self { pos, ori }
         }
 
+        /// Creates a new position with the orientation given by euler angles of the given sequence (see `EulerOrder`)
+        pub fn new_euler(pos : Vec3, order : EulerOrder, angles : [f32; 3]) -> Self {
+            Self::new_ori(pos, order.to_mat3(angles))
+        }
+
+        /// Creates a new position with the orientation given by roll (X), pitch (Y) and yaw (Z) angles around the
+        /// fixed axes
+        pub fn new_rpy(pos : Vec3, roll : f32, pitch : f32, yaw : f32) -> Self {
+            Self::new_ori(pos, rpy_to_mat3(roll, pitch, yaw))
+        }
+
+        /// Creates a new position with the orientation given by a quaternion (normalized before use)
+        pub fn new_quat(pos : Vec3, quat : Quat) -> Self {
+            Self::new_ori(pos, Mat3::from_quat(quat.normalize()))
+        }
+
+        /// Creates a new position with the orientation given by a rotation of `angle` around `axis`
+        pub fn new_axis_angle(pos : Vec3, axis : Vec3, angle : f32) -> Self {
+            Self::new_ori(pos, Mat3::from_axis_angle(axis.normalize(), angle))
+        }
+
+        // Orientation accessors
+            /// Returns the euler angles of the orientation in the given sequence (see `EulerOrder::angles()`)
+            pub fn euler(&self, order : EulerOrder) -> [f32; 3] {
+                order.angles(&self.ori)
+            }
+
+            /// Returns the roll (X), pitch (Y) and yaw (Z) angles of the orientation around the fixed axes
+            pub fn rpy(&self) -> [f32; 3] {
+                mat3_to_rpy(&self.ori)
+            }
+
+            /// Returns the orientation as quaternion
+            pub fn quat(&self) -> Quat {
+                Quat::from_mat3(&self.ori).normalize()
+            }
+
+            /// Returns the orientation as rotation axis and angle, the angle lies in the range [0, PI]
+            pub fn axis_angle(&self) -> (Vec3, f32) {
+                let (axis, angle) = self.quat().to_axis_angle();
+
+                if angle > PI {
+                    (-axis, 2.0 * PI - angle)
+                } else {
+                    (axis, angle)
+                }
+            }
+        // 
+
         pub fn to_wo(self) -> WorldObj {
             WorldObj::from_pos(self)
         }
@@ -128,6 +225,7 @@ pub trait Point : Debug {
 
         fn transform(&mut self, by : Mat3) {
             self.pos = by * self.pos;
+            self.ori = by * self.ori;
         }
 
         fn as_wo<'a>(&self) -> Option<&WorldObj> {
@@ -146,11 +244,18 @@ pub trait Point : Debug {
     }
 // 
 
+/// A shared reference to a point of the coordinate system, the reference can be sent and shared between threads and
+/// tasks. The point is guarded by a readers-writer lock (see `PointCell`), nested reads on the same thread always 
+/// succeed, nested borrows that would deadlock cause a panic (like the borrows of a `RefCell`), `try_borrow()` and 
+/// `try_borrow_mut()` return an error instead
+/// 
+/// Observers can subscribe to changes of the pose of the point, they are notified whenever a mutable borrow that 
+/// changed the pose is released
 #[derive(Clone, Debug)]
-pub struct PointRef(pub Rc<RefCell<dyn Point>>);
+pub struct PointRef(pub Arc<PointCell>);
 
 impl Deref for PointRef {
-    type Target = Rc<RefCell<dyn Point>>;
+    type Target = Arc<PointCell>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -165,20 +270,81 @@ impl DerefMut for PointRef {
 
 impl PointRef {
     pub fn new<T : Point + 'static>(point : T) -> Self {
-        Self(Rc::new(RefCell::new(point)))
+        Self(Arc::new(PointCell::new(point)))
+    }
+
+    /// Locks the point for reading, nested reads on the same thread always succeed
+    /// 
+    /// # Panics
+    /// 
+    /// If the point is mutably borrowed by the current thread already
+    pub fn borrow(&self) -> PointReadGuard<'_> {
+        self.0.read()
+    }
+
+    /// Locks the point for reading, returns an error if the point is mutably borrowed by the current thread already
+    pub fn try_borrow(&self) -> Result<PointReadGuard<'_>, crate::Error> {
+        self.0.try_read()
+    }
+
+    /// Locks the point for writing, the observers of the point are notified when the guard is dropped and the pose has
+    /// changed
+    /// 
+    /// # Panics
+    /// 
+    /// If the point is borrowed by the current thread already
+    pub fn borrow_mut(&self) -> PointGuard<'_> {
+        self.0.write()
+    }
+
+    /// Locks the point for writing, returns an error if the point is borrowed by the current thread already
+    pub fn try_borrow_mut(&self) -> Result<PointGuard<'_>, crate::Error> {
+        self.0.try_write()
     }
 
+    // Observers
+        /// Subscribes to changes of the pose of the point, the callback receives the new pose (in the coordinates of
+        /// the parent system)
+        pub fn subscribe<F>(&self, callback : F) -> ObserverId
+        where
+            F : Fn(&Position) + Send + Sync + 'static
+        {
+            self.0.observe(move |pose| {
+                callback(pose);
+                true
+            })
+        }
+
+        /// Subscribes to changes of the pose of the point using a channel, the subscription ends once the receiver is
+        /// dropped
+        pub fn subscribe_channel(&self) -> UnboundedReceiver<Position> {
+            let (tx, rx) = unbounded_channel();
+            self.0.observe(move |pose| tx.send(pose.clone()).is_ok());
+            rx
+        }
+    // 
+
     pub fn pos(&self) -> Vec3 {
         *self.borrow().pos()
     }
 
+    /// Returns a copy of the pose of the referenced point
+    pub fn pose(&self) -> Position {
+        self.borrow().pose()
+    }
+
+    /// Applies the given rigid transformation to the referenced point, see `Point::apply()`
+    pub fn apply(&self, tf : &Transform) {
+        self.borrow_mut().apply(tf)
+    }
+
     pub fn clone_no_ref(&self) -> PointRef {
         let p = self.borrow(); 
 
         if let Some(wo) = p.as_wo() {
-            PointRef(Rc::new(RefCell::new(wo.clone())))
+            PointRef::new(wo.clone())
         } else if let Some(pos) = p.as_pos() { 
-            PointRef(Rc::new(RefCell::new(pos.clone())))
+            PointRef::new(pos.clone())
         } else {
             panic!("Bad implementation of trait 'Point'!");
         }
@@ -188,7 +354,13 @@ impl PointRef {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct WorldObj {
     pos : Position,
-    pub sub : HashMap<String, PointRef>
+    sub : HashMap<String, PointRef>,
+    /// Collision and visual geometry attached to the object
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub geometry : Vec<Geometry>,
+    /// The unit of all lengths in the tree, declared once per file instead of per object
+    #[serde(skip)]
+    unit : LengthUnit
 }
 
 impl AsRef<Position> for WorldObj {
@@ -236,10 +408,26 @@ impl Point for WorldObj {
         self.pos.transform(by)
     }
 
+    fn scale(&mut self, factor : f32) {
+        self.pos.scale(factor);
+
+        for geom in self.geometry.iter_mut() {
+            geom.scale(factor);
+        }
+
+        for point in self.sub.values() {
+            point.borrow_mut().scale(factor);
+        }
+    }
+
     fn as_wo<'a>(&'a self) -> Option<&'a WorldObj> {
         Some(self)
     }
 
+    fn as_wo_mut(&mut self) -> Option<&mut WorldObj> {
+        Some(self)
+    }
+
     fn as_pos<'a>(&'a self) -> Option<&'a Position> {
         Some(&self.pos)
     }
@@ -260,27 +448,67 @@ impl WorldObj {
     pub fn from_pos(pos : Position) -> Self {
         Self {
             pos,
-            sub: HashMap::new()
+            sub: HashMap::new(),
+            geometry: Vec::new(),
+            unit: LengthUnit::default()
         }
     }
 
+    /// Creates a new `WorldObj` in millimetres with the given sub-points
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if one of the sub-points is a world object using another length unit
     pub fn from_pos_sub(pos : Position, sub : HashMap<String, PointRef>) -> Self {
-        Self {
-            pos, sub
+        let wobj = Self::from_pos(pos);
+
+        for (name, point) in sub.iter() {
+            if let Err(err) = wobj.check_unit(name, &*point.borrow()) {
+                panic!("{}", err);
+            }
         }
+
+        Self { sub, ..wobj }
     }
 
-    pub fn add_point<N : Into<String>>(&mut self, name : N, point : PointRef) {
-        let name_str = name.into();
-        if name_str.contains('/') {
-            panic!("Bad point name! Point names must not contain '/'! (Name: {})", name_str);
+    /// The sub-points of this `WorldObj`, use `add_point()`, `remove_point()` and the other editing functions to 
+    /// change them
+    #[inline]
+    pub fn sub(&self) -> &HashMap<String, PointRef> {
+        &self.sub
+    }
+
+    /// Checks whether the given name can be used for a point
+    pub(crate) fn check_name(name : &str) -> Result<(), crate::Error> {
+        if name.is_empty() {
+            Err("Bad point name! Point names must not be empty!".into())
+        } else if name.contains('/') {
+            Err(format!("Bad point name! Point names must not contain '/'! (Name: {})", name).into())
+        } else {
+            Ok(())
         }
+    }
+
+    /// Adds a point with the given name, an existing point with the same name is replaced. World objects must use the
+    /// same length unit as this `WorldObj`
+    pub fn add_point<N : Into<String>>(&mut self, name : N, point : PointRef) -> Result<(), crate::Error> {
+        let name_str = name.into();
+        Self::check_name(&name_str)?;
+        self.check_unit(&name_str, &*point.borrow())?;
         
         self.sub.insert(name_str, point);
+        Ok(())
     }
 
+    /// Builder variant of `add_point()`
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the name is not a valid point name (empty or containing '/')
     pub fn add_point_inline<N : Into<String>>(mut self, name : N, point : PointRef) -> Self {
-        self.add_point(name, point);
+        if let Err(err) = self.add_point(name, point) {
+            panic!("{}", err);
+        }
         self
     }
 
@@ -312,7 +540,7 @@ impl WorldObj {
     }
 
     fn resolve_path_step(&self, split : &[&str], mut index : usize) -> Option<PointRef> {
-        if index > split.len() {
+        if index >= split.len() {
             return None;
         }
 
@@ -333,30 +561,205 @@ impl WorldObj {
         None
     }
 
-    // fn trans_pos_step(&self, split : &[String], index : usize) -> Option<Vec3> {
-    //     if index > split.len() {
-    //         return None;
-    //     }
+    /// Returns the pose of the point with the given path in world coordinates, composing position and orientation of
+    /// every system along the path, including the pose of this `WorldObj` itself. An empty path returns the pose of
+    /// this `WorldObj`
+    pub fn world_pose<S : Into<String>>(&self, path : S) -> Option<Position> {
+        let path_s = path.into();
+        let mut pose = self.pos.clone();
+
+        if path_s.is_empty() {
+            return Some(pose);
+        }
+
+        let mut current : Option<PointRef> = None;
+
+        for name in path_s.split('/') {
+            let next = match &current {
+                None => self.sub.get(name).cloned(),
+                Some(point) => point.borrow().as_wo().and_then(|wo| wo.sub.get(name).cloned())
+            }?;
+
+            pose = math::chain_poses(&pose, &next.pose());
+
+            current = Some(next);
+        }
+
+        Some(pose)
+    }
+
+    /// Returns the pose of the point `to_path` in the coordinates of the point `from_path`
+    pub fn relative_pose<F : Into<String>, T : Into<String>>(&self, from_path : F, to_path : T) -> Option<Position> {
+        let from = self.world_pose(from_path)?;
+        let to = self.world_pose(to_path)?;
+
+        Some(math::chain_poses(&math::invert_pose(&from), &to))
+    }
+
+    // Editing
+        /// Splits the given path into the path of the parent (empty for this `WorldObj`) and the name of the point
+        fn split_path(path : &str) -> (&str, &str) {
+            path.rsplit_once('/').unwrap_or(("", path))
+        }
+
+        /// Calls the given function with the `WorldObj` at the given path (this `WorldObj` if the path is empty)
+        fn with_wo_mut<R, F>(&mut self, path : &str, func : F) -> Result<R, crate::Error>
+        where
+            F : FnOnce(&mut WorldObj) -> Result<R, crate::Error>
+        {
+            if path.is_empty() {
+                return func(self);
+            }
+
+            let point = self.req_point(path)?;
+            let mut p = point.borrow_mut();
+
+            match p.as_wo_mut() {
+                Some(wo) => func(wo),
+                None => Err(format!("The point '{}' is not a world object", path).into())
+            }
+        }
+
+        /// Removes the point with the given path, returns the removed point
+        pub fn remove_point(&mut self, path : &str) -> Result<PointRef, crate::Error> {
+            let (parent, name) = Self::split_path(path);
+
+            self.with_wo_mut(parent, |wo| {
+                wo.sub.remove(name).ok_or_else(|| format!("The point '{}' does not exist", path).into())
+            })
+        }
+
+        /// Renames the point with the given path, the point stays in the same parent system
+        pub fn rename_point(&mut self, path : &str, new_name : &str) -> Result<(), crate::Error> {
+            Self::check_name(new_name)?;
+            let (parent, name) = Self::split_path(path);
+
+            self.with_wo_mut(parent, |wo| {
+                if wo.sub.contains_key(new_name) {
+                    return Err(format!("A point with the name '{}' already exists in '{}'", new_name, parent).into());
+                }
 
-    //     if let Some(point) = self.sub.get(&split[index]) {
-    //         let p = point.borrow();
+                let point = wo.sub.remove(name).ok_or_else(|| format!("The point '{}' does not exist", path))?;
+                wo.sub.insert(new_name.to_owned(), point);
+                Ok(())
+            })
+        }
+
+        /// Moves the point with the given path into the `WorldObj` at `new_parent` (an empty path refers to this 
+        /// `WorldObj`), the pose of the point is adjusted so that its world pose stays the same. Returns the new path
+        pub fn reparent(&mut self, path : &str, new_parent : &str) -> Result<String, crate::Error> {
+            let (_, name) = Self::split_path(path);
 
-    //         if split.len() == index {
-    //             return Some((*self.ori()) * (*p.pos()) + *self.pos());
-    //         }
+            if (new_parent == path) || new_parent.starts_with(&format!("{}/", path)) {
+                return Err(format!("Cannot move the point '{}' into itself ('{}')", path, new_parent).into());
+            }
 
-    //         if let Some(wo) = p.as_wo() {
-    //             if let Some(pos) = wo.trans_pos_step(split, index + 1) {
-    //                 return Some((*self.ori()) * (pos) + *self.pos());
-    //             } 
-    //         } 
-    //     } 
+            let new_path = if new_parent.is_empty() { name.to_owned() } else { format!("{}/{}", new_parent, name) };
+
+            if self.point(new_path.as_str()).is_some() {
+                return Err(format!("The point '{}' already exists", new_path).into());
+            }
+
+            let pose = self.relative_pose(new_parent, path)
+                .ok_or_else(|| format!("The points '{}' and '{}' must both exist", new_parent, path))?;
+
+            // Check the parent before removing the point, so the tree stays untouched on errors
+            self.with_wo_mut(new_parent, |_| Ok(()))?;
+
+            let point = self.remove_point(path)?;
+            {
+                let mut p = point.borrow_mut();
+                *p.pos_mut() = *pose.pos();
+                *p.ori_mut() = *pose.ori();
+            }
 
-    //     None
-    // }
+            self.with_wo_mut(new_parent, |wo| wo.add_point(name, point))?;
+            Ok(new_path)
+        }
+    //
+
+    // Traversal
+        /// Returns all points of the tree depth-first with their full paths, the points of each system are sorted by
+        /// their names
+        pub fn walk(&self) -> Vec<(String, PointRef)> {
+            let mut points = Vec::new();
+            self.walk_step("", &mut points);
+            points
+        }
+
+        fn walk_step(&self, prefix : &str, points : &mut Vec<(String, PointRef)>) {
+            let mut names : Vec<&String> = self.sub.keys().collect();
+            names.sort();
+
+            for name in names {
+                let point = &self.sub[name];
+                let path = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+
+                points.push((path.clone(), point.clone()));
+
+                if let Some(wo) = point.borrow().as_wo() {
+                    wo.walk_step(&path, points);
+                }
+            }
+        }
 
-    // pub fn trans_pos(&self, path : String) -> Option<Vec3> {
-    //     let path_split : Vec<String> = path.split('/').map(|elem| elem.to_owned()).collect();
-    //     self.trans_pos_step(&path_split, 0)
-    // }
+        /// Returns all points whose path matches the given glob pattern, e.g. `fixtures/*/origin`. Within a path 
+        /// segment `*` matches any number of characters and `?` matches a single character, a segment `**` matches 
+        /// any number of segments
+        pub fn query(&self, pattern : &str) -> Result<Vec<(String, PointRef)>, crate::Error> {
+            let segments : Vec<&str> = pattern.split('/').collect();
+
+            if segments.iter().any(|seg| seg.is_empty()) {
+                return Err(format!("Bad query pattern '{}'! Segments must not be empty!", pattern).into());
+            }
+
+            Ok(self.walk().into_iter().filter(|(path, _)| {
+                let path_split : Vec<&str> = path.split('/').collect();
+                glob_path(&segments, &path_split)
+            }).collect())
+        }
+
+        /// Subscribes to changes of all points currently in the tree, every change is sent with the path of the point.
+        /// Points added afterwards are not observed, the subscription ends once the receiver is dropped
+        /// 
+        /// Points moved by a kinematic (see `KinElement`) keep their base pose in the tree, their observers receive the
+        /// moved pose with every update of the kinematic
+        pub fn subscribe_all(&self) -> UnboundedReceiver<PoseChange> {
+            let (tx, rx) = unbounded_channel();
+
+            for (path, point) in self.walk() {
+                let tx = tx.clone();
+                point.observe(move |pose| tx.send(PoseChange { path: path.clone(), pose: pose.clone() }).is_ok());
+            }
+
+            rx
+        }
+    //
+}
+
+
+/// Checks if the path segments match the glob pattern segments
+fn glob_path(pattern : &[&str], path : &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0 ..= path.len()).any(|skip| glob_path(rest, &path[skip ..])),
+        Some((seg, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                let seg_chars : Vec<char> = seg.chars().collect();
+                let name_chars : Vec<char> = name.chars().collect();
+                glob_name(&seg_chars, &name_chars) && glob_path(rest, path_rest)
+            },
+            None => false
+        }
+    }
+}
+
+/// Checks if the name matches the glob pattern (`*` and `?` wildcards)
+fn glob_name(pattern : &[char], name : &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0 ..= name.len()).any(|skip| glob_name(rest, &name[skip ..])),
+        Some(('?', rest)) => !name.is_empty() && glob_name(rest, &name[1 ..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_name(rest, &name[1 ..])
+    }
 }
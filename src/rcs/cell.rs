@@ -0,0 +1,275 @@
+use core::cell::UnsafeCell;
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+use alloc::sync::Arc;
+
+use crate::rcs::{Point, Position};
+
+/// Observer of a point, returning `false` removes the observer
+type Observer = Arc<dyn Fn(&Position) -> bool + Send + Sync>;
+
+static NEXT_OBSERVER_ID : AtomicUsize = AtomicUsize::new(0);
+
+/// Identifier of an observer, used to unsubscribe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObserverId(usize);
+
+/// A change of the pose of a point in a `WorldObj` tree, see `WorldObj::subscribe_all()`
+#[derive(Clone, Debug)]
+pub struct PoseChange {
+    /// The path of the point that changed
+    pub path : String,
+    /// The new pose of the point (in the coordinates of its parent)
+    pub pose : Position
+}
+
+/// Lock state of a point
+#[derive(Default)]
+struct LockState {
+    /// Threads holding a read lock (one entry per guard)
+    readers : Vec<ThreadId>,
+    /// Thread holding the write lock
+    writer : Option<ThreadId>,
+    /// Number of threads waiting for the write lock, new readers wait for them to avoid starving writers
+    writers_waiting : usize
+}
+
+/// The shared storage of a point, holding the point itself and the observers that are notified when the pose of the
+/// point changes
+/// 
+/// The point is guarded by a readers-writer lock that knows the threads holding it: nested reads on the same thread 
+/// always succeed (like the borrows of a `RefCell`), nested writes or reads inside of a write are rejected instead of
+/// deadlocking
+pub struct PointCell<P : ?Sized = dyn Point> {
+    observers : Mutex<Vec<(ObserverId, Observer)>>,
+    state : Mutex<LockState>,
+    released : Condvar,
+    point : UnsafeCell<P>
+}
+
+// The point is only accessed through the guards, which follow the lock state
+unsafe impl<P : ?Sized + Send + Sync> Sync for PointCell<P> { }
+
+impl<P : Point> PointCell<P> {
+    /// Creates a new cell without any observers
+    pub fn new(point : P) -> Self {
+        Self {
+            observers: Mutex::new(Vec::new()),
+            state: Mutex::new(LockState::default()),
+            released: Condvar::new(),
+            point: UnsafeCell::new(point)
+        }
+    }
+}
+
+impl PointCell {
+    /// Locks the point for reading, nested reads on the same thread always succeed
+    /// 
+    /// # Panics
+    /// 
+    /// If the current thread holds the write lock of the point (nested borrow), see `try_read()`
+    pub fn read(&self) -> PointReadGuard<'_> {
+        self.try_read().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Locks the point for reading, returns an error instead of deadlocking if the current thread holds the write lock
+    /// of the point. Nested reads on the same thread always succeed, locks held by other threads are waited for
+    pub fn try_read(&self) -> Result<PointReadGuard<'_>, crate::Error> {
+        let id = thread::current().id();
+        let mut state = self.lock_state();
+
+        if state.writer == Some(id) {
+            return Err(NESTED_BORROW.into());
+        }
+
+        // The point cannot be written while the thread holds a read lock, so nested reads do not wait for writers
+        if !state.readers.contains(&id) {
+            while state.writer.is_some() || (state.writers_waiting > 0) {
+                state = self.released.wait(state).unwrap_or_else(|err| err.into_inner());
+            }
+        }
+
+        state.readers.push(id);
+        Ok(PointReadGuard { cell: self, __pd: PhantomData })
+    }
+
+    /// Locks the point for writing, the observers are notified once the guard is dropped and the pose has changed
+    /// 
+    /// # Panics
+    /// 
+    /// If the current thread holds a lock of the point already (nested borrow), see `try_write()`
+    pub fn write(&self) -> PointGuard<'_> {
+        self.try_write().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Locks the point for writing, returns an error instead of deadlocking if the current thread holds a lock of the
+    /// point already. Locks held by other threads are waited for
+    pub fn try_write(&self) -> Result<PointGuard<'_>, crate::Error> {
+        let id = thread::current().id();
+        let mut state = self.lock_state();
+
+        if (state.writer == Some(id)) || state.readers.contains(&id) {
+            return Err(NESTED_BORROW.into());
+        }
+
+        state.writers_waiting += 1;
+
+        while state.writer.is_some() || !state.readers.is_empty() {
+            state = self.released.wait(state).unwrap_or_else(|err| err.into_inner());
+        }
+
+        state.writers_waiting -= 1;
+        state.writer = Some(id);
+        drop(state);
+
+        let mut guard = PointGuard { cell: self, before: None, __pd: PhantomData };
+
+        if self.has_observers() {
+            guard.before = Some(guard.pose());
+        }
+
+        Ok(guard)
+    }
+
+    // Lock state
+        fn lock_state(&self) -> MutexGuard<'_, LockState> {
+            self.state.lock().unwrap_or_else(|err| err.into_inner())
+        }
+
+        fn release_read(&self) {
+            let id = thread::current().id();
+            let mut state = self.lock_state();
+
+            if let Some(index) = state.readers.iter().position(|reader| *reader == id) {
+                state.readers.swap_remove(index);
+            }
+
+            self.released.notify_all();
+        }
+
+        fn release_write(&self) {
+            self.lock_state().writer = None;
+            self.released.notify_all();
+        }
+    //
+
+    /// Returns whether or not any observers are registered
+    pub fn has_observers(&self) -> bool {
+        !self.observers.lock().unwrap_or_else(|err| err.into_inner()).is_empty()
+    }
+
+    /// Registers a raw observer, the observer is removed once it returns `false`
+    pub(crate) fn observe<F>(&self, observer : F) -> ObserverId
+    where
+        F : Fn(&Position) -> bool + Send + Sync + 'static
+    {
+        let id = ObserverId(NEXT_OBSERVER_ID.fetch_add(1, Ordering::Relaxed));
+        self.observers.lock().unwrap_or_else(|err| err.into_inner()).push((id, Arc::new(observer)));
+        id
+    }
+
+    /// Removes the observer with the given id, returns `false` if no such observer exists
+    pub fn unsubscribe(&self, id : ObserverId) -> bool {
+        let mut observers = self.observers.lock().unwrap_or_else(|err| err.into_inner());
+        let len = observers.len();
+        observers.retain(|(obs_id, _)| *obs_id != id);
+        observers.len() != len
+    }
+
+    /// Notifies all observers about the given pose
+    pub(crate) fn notify(&self, pose : &Position) {
+        // Copy the observers, so they can (un-)subscribe or access the point themselves
+        let observers : Vec<(ObserverId, Observer)> = self.observers.lock().unwrap_or_else(|err| err.into_inner()).clone();
+
+        for (id, observer) in observers {
+            if !observer(pose) {
+                self.unsubscribe(id);
+            }
+        }
+    }
+}
+
+impl Debug for PointCell {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let id = thread::current().id();
+        let state = self.lock_state();
+
+        // Only print the point if it can be read without waiting
+        if state.readers.contains(&id) || (state.writer.is_none() && (state.writers_waiting == 0)) {
+            drop(state);
+            let point = self.read();
+            f.debug_struct("PointCell").field("point", &&*point).finish()
+        } else {
+            f.debug_struct("PointCell").field("point", &"<locked>").finish()
+        }
+    }
+}
+
+const NESTED_BORROW : &str = "The point is borrowed already by the current thread, nested borrows of a point would deadlock";
+
+/// Read guard of a point, the guard belongs to the thread that created it
+pub struct PointReadGuard<'a> {
+    cell : &'a PointCell,
+    __pd : PhantomData<*const ()>
+}
+
+impl<'a> Deref for PointReadGuard<'a> {
+    type Target = dyn Point + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        // Safety: The thread holds a read lock, the point is not written until it is released
+        unsafe { &*self.cell.point.get() }
+    }
+}
+
+impl<'a> Drop for PointReadGuard<'a> {
+    fn drop(&mut self) {
+        self.cell.release_read();
+    }
+}
+
+/// Write guard of a point, notifies the observers of the point when dropped, if the pose of the point has changed. The 
+/// guard belongs to the thread that created it
+pub struct PointGuard<'a> {
+    cell : &'a PointCell,
+    before : Option<Position>,
+    __pd : PhantomData<*const ()>
+}
+
+impl<'a> Deref for PointGuard<'a> {
+    type Target = dyn Point + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        // Safety: The thread holds the write lock
+        unsafe { &*self.cell.point.get() }
+    }
+}
+
+impl<'a> DerefMut for PointGuard<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // Safety: The thread holds the write lock, no other guards exist
+        unsafe { &mut *self.cell.point.get() }
+    }
+}
+
+impl<'a> Drop for PointGuard<'a> {
+    fn drop(&mut self) {
+        let after = self.before.is_some().then(|| self.pose());
+
+        // Release the lock before notifying, so observers can access the point
+        self.cell.release_write();
+
+        let (Some(before), Some(after)) = (self.before.take(), after) else {
+            return;
+        };
+
+        if after != before {
+            self.cell.notify(&after);
+        }
+    }
+}
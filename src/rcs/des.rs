@@ -1,23 +1,23 @@
-use core::cell::RefCell;
 use std::collections::HashMap;
 
-use alloc::rc::Rc;
 use glam::{Vec3, Mat3};
 use serde::{Serialize, Deserialize};
 
-use crate::rcs::{Position, WorldObj, PointRef, Point};
+use crate::rcs::{Geometry, Position, WorldObj, PointRef, Point};
+use crate::rcs::ori::OriDes;
 
+/// Serialized form of a `Position`, the orientation can be given in any of the representations of `OriDes`
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct PositionDes {
     pub pos : [f32; 3],
-    pub ori : Option<[[f32; 3]; 3]>
+    pub ori : Option<OriDes>
 }
 
 impl From<Position> for PositionDes {
     fn from(pos : Position) -> Self {
         PositionDes { 
             pos: pos.pos.to_array(), 
-            ori: Some(pos.ori.to_cols_array_2d())
+            ori: Some(OriDes::from(&pos.ori))
         }
     }
 }
@@ -27,7 +27,7 @@ impl Into<Position> for PositionDes {
         if let Some(ori) = &self.ori {
             Position::new_ori(
                 Vec3::from(self.pos),
-                Mat3::from_cols_array_2d(ori)
+                Mat3::from(ori)
             )
         } else {
             Position::from_vec3(
@@ -57,9 +57,22 @@ impl<'de> Deserialize<'de> for Position {
 #[derive(Serialize, Deserialize)]
 #[serde(untagged)]
 enum PointEnum {
-    Pos { ori : Option<[[f32; 3]; 3]>, pos : [f32; 3] }, 
-    Wo { pos : Position, sub : HashMap<String, PointRef> },
-    WoDir { pos: [f32; 3], sub : HashMap<String, PointRef> }
+    // World objects first: untagged variants are tried in order and unknown fields are ignored, so a leading `Pos` 
+    // variant would match every world object given with a plain `pos` array, silently dropping its `sub` points (see 
+    // the `point_deserialization` test)
+    Wo { 
+        pos : Position, 
+        sub : HashMap<String, PointRef>, 
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        geometry : Vec<Geometry> 
+    },
+    WoDir { 
+        pos: [f32; 3], 
+        sub : HashMap<String, PointRef>, 
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        geometry : Vec<Geometry> 
+    },
+    Pos { ori : Option<OriDes>, pos : [f32; 3] }
 }
 
 impl From<PointRef> for PointEnum {
@@ -68,15 +81,15 @@ impl From<PointRef> for PointEnum {
 
         if let Some(wo) = poi.as_wo() {
             if wo.pos.ori == Mat3::IDENTITY {
-                Self::WoDir { pos: wo.pos.pos().to_array(), sub: wo.sub.clone() }
+                Self::WoDir { pos: wo.pos.pos().to_array(), sub: wo.sub.clone(), geometry: wo.geometry.clone() }
             } else {
-                Self::Wo { pos: wo.pos.clone(), sub: wo.sub.clone() }
+                Self::Wo { pos: wo.pos.clone(), sub: wo.sub.clone(), geometry: wo.geometry.clone() }
             }
         } else if let Some(pos) = poi.as_pos() {
             if pos.ori == Mat3::IDENTITY {
                 Self::Pos { pos: pos.pos.to_array(), ori: None }
             } else {
-                Self::Pos { ori: Some(pos.ori.to_cols_array_2d()), pos: pos.pos.to_array() }
+                Self::Pos { ori: Some(OriDes::from(&pos.ori)), pos: pos.pos.to_array() }
             }
         } else {
             panic!("Bad implementation of the trait 'Point'!")
@@ -100,15 +113,15 @@ impl<'de> Deserialize<'de> for PointRef {
         
         Ok(match point {
             PointEnum::Pos { ori, pos } => 
-                PointRef(Rc::new(RefCell::new(Position::new_ori(Vec3::from(pos), if let Some(o) = &ori {
-                    Mat3::from_cols_array_2d(o)
+                PointRef::new(Position::new_ori(Vec3::from(pos), if let Some(o) = &ori {
+                    Mat3::from(o)
                 } else {
                     Mat3::IDENTITY
-                })))),
-            PointEnum::Wo { pos, sub } => 
-                PointRef(Rc::new(RefCell::new(WorldObj::from_pos_sub(pos, sub)))),
-            PointEnum::WoDir { pos, sub } => 
-                PointRef(Rc::new(RefCell::new(WorldObj::from_pos_sub(Position::from_vec3(Vec3::from(pos)), sub))))
+                })),
+            PointEnum::Wo { pos, sub, geometry } => 
+                PointRef::new(WorldObj { geometry, ..WorldObj::from_pos_sub(pos, sub) }),
+            PointEnum::WoDir { pos, sub, geometry } => 
+                PointRef::new(WorldObj { geometry, ..WorldObj::from_pos_sub(Position::from_vec3(Vec3::from(pos)), sub) })
         })
     }
 }
\ No newline at end of file
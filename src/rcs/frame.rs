@@ -0,0 +1,48 @@
+use serde::{Serialize, Deserialize};
+
+use crate::rcs::{Position, WorldObj};
+use crate::rcs::math::chain_poses;
+
+/// A position given relative to a named frame (work object) of the `WorldObj` tree, e.g. `fixtures/pallet1`
+/// 
+/// The frame is resolved every time the position is used, so moving or re-teaching a frame updates all the positions
+/// that refer to it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FramedPosition {
+    /// Path of the frame in the `WorldObj` tree, `None` refers to the base coordinates
+    #[serde(default)]
+    pub frame : Option<String>,
+    /// The position in the coordinates of the frame
+    pub pos : Position
+}
+
+impl FramedPosition {
+    /// Creates a new position relative to the frame with the given path
+    pub fn new<S : Into<String>>(frame : S, pos : Position) -> Self {
+        Self { frame: Some(frame.into()), pos }
+    }
+
+    /// Creates a new position in base coordinates
+    pub fn base(pos : Position) -> Self {
+        Self { frame: None, pos }
+    }
+
+    /// Converts the position into base coordinates, the coordinates of the given `WorldObj` (the pose of the 
+    /// `WorldObj` itself is not included)
+    pub fn resolve(&self, wobj : &WorldObj) -> Result<Position, crate::Error> {
+        if let Some(frame) = &self.frame {
+            let frame_pose = wobj.relative_pose("", frame.as_str())
+                .ok_or_else(|| format!("The frame '{}' does not exist", frame))?;
+
+            Ok(chain_poses(&frame_pose, &self.pos))
+        } else {
+            Ok(self.pos.clone())
+        }
+    }
+}
+
+impl From<Position> for FramedPosition {
+    fn from(pos : Position) -> Self {
+        Self::base(pos)
+    }
+}
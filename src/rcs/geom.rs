@@ -0,0 +1,375 @@
+use std::fs;
+use std::path::Path;
+
+use glam::Vec3;
+use serde::{Serialize, Deserialize};
+use serde::ser::SerializeStruct;
+
+use crate::rcs::{Point, Position, WorldObj};
+
+// Shapes
+    /// The shape of a geometry, all dimensions are given in the coordinates of the geometry
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type", rename_all = "lowercase")]
+    pub enum Shape {
+        /// Box centered around the origin
+        Box {
+            /// Edge lengths in X, Y and Z direction
+            size : [f32; 3]
+        },
+        /// Sphere centered around the origin
+        Sphere {
+            radius : f32
+        },
+        /// Cylinder centered around the origin, with the axis along Z
+        Cylinder {
+            radius : f32,
+            /// Length of the cylinder along the Z-axis
+            height : f32
+        },
+        /// Capsule (cylinder with hemispherical caps) centered around the origin, with the axis along Z
+        Capsule {
+            radius : f32,
+            /// Length of the cylindrical part along the Z-axis (without the caps)
+            height : f32
+        },
+        /// Triangle mesh
+        Mesh(Mesh)
+    }
+
+    impl Shape {
+        /// Returns the axis aligned bounding box (min, max) of the shape in its own coordinates
+        pub fn aabb(&self) -> (Vec3, Vec3) {
+            match self {
+                Self::Box { size } => (-Vec3::from(*size) / 2.0, Vec3::from(*size) / 2.0),
+                Self::Sphere { radius } => (Vec3::splat(-*radius), Vec3::splat(*radius)),
+                Self::Cylinder { radius, height } =>
+                    (Vec3::new(-*radius, -*radius, -height / 2.0), Vec3::new(*radius, *radius, height / 2.0)),
+                Self::Capsule { radius, height } =>
+                    (Vec3::new(-*radius, -*radius, -height / 2.0 - radius), Vec3::new(*radius, *radius, height / 2.0 + radius)),
+                Self::Mesh(mesh) => mesh.aabb()
+            }
+        }
+
+        /// Scales all dimensions of the shape by the given factor, used to convert between length units
+        pub fn scale(&mut self, factor : f32) {
+            match self {
+                Self::Box { size } => size.iter_mut().for_each(|s| *s *= factor),
+                Self::Sphere { radius } => *radius *= factor,
+                Self::Cylinder { radius, height } | Self::Capsule { radius, height } => {
+                    *radius *= factor;
+                    *height *= factor;
+                },
+                Self::Mesh(mesh) => mesh.scale(factor)
+            }
+        }
+    }
+//
+
+// Mesh
+    /// A triangle mesh, meshes loaded from a file are serialized by reference to the file
+    #[derive(Clone, Debug, PartialEq, Deserialize)]
+    pub struct Mesh {
+        /// The file the mesh has been loaded from, if set the vertices and triangles are not serialized
+        #[serde(default)]
+        pub file : Option<String>,
+        /// Factor the vertices of the file are scaled with, e.g. to convert a mesh given in metres into millimetres
+        #[serde(default = "default_scale")]
+        pub scale : f32,
+        /// The vertices of the mesh
+        #[serde(default)]
+        pub vertices : Vec<[f32; 3]>,
+        /// Indices of the three vertices of each triangle
+        #[serde(default)]
+        pub triangles : Vec<[u32; 3]>
+    }
+
+    fn default_scale() -> f32 {
+        1.0
+    }
+
+    impl Default for Mesh {
+        fn default() -> Self {
+            Self { file: None, scale: 1.0, vertices: Vec::new(), triangles: Vec::new() }
+        }
+    }
+
+    impl Serialize for Mesh {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer {
+            if let Some(file) = &self.file {
+                let mut state = serializer.serialize_struct("Mesh", 2)?;
+                state.serialize_field("file", file)?;
+
+                if self.scale != 1.0 {
+                    state.serialize_field("scale", &self.scale)?;
+                } else {
+                    state.skip_field("scale")?;
+                }
+
+                state.end()
+            } else {
+                let mut state = serializer.serialize_struct("Mesh", 2)?;
+                state.serialize_field("vertices", &self.vertices)?;
+                state.serialize_field("triangles", &self.triangles)?;
+                state.end()
+            }
+        }
+    }
+
+    impl Mesh {
+        /// Creates a new mesh out of the given vertices and triangles
+        pub fn new(vertices : Vec<[f32; 3]>, triangles : Vec<[u32; 3]>) -> Self {
+            Self { file: None, scale: 1.0, vertices, triangles }
+        }
+
+        /// Loads a mesh out of a STL (binary or ASCII) or OBJ file, selected by the file extension
+        pub fn load<P : AsRef<Path>>(path : P) -> Result<Self, crate::Error> {
+            let path = path.as_ref();
+            let data = fs::read(path)
+                .map_err(|err| format!("Failed to read mesh file '{}': {}", path.display(), err))?;
+
+            let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("").to_lowercase();
+
+            let mut mesh = match ext.as_str() {
+                "stl" => Self::parse_stl(&data),
+                "obj" => Self::parse_obj(&String::from_utf8_lossy(&data)),
+                _ => Err(format!("Unsupported mesh format '{}'", ext).into())
+            }.map_err(|err| format!("Bad mesh file '{}': {}", path.display(), err))?;
+
+            mesh.file = Some(path.to_string_lossy().into_owned());
+            Ok(mesh)
+        }
+
+        /// Parses a binary or ASCII STL file
+        pub fn parse_stl(data : &[u8]) -> Result<Self, crate::Error> {
+            // Binary files have an 80 byte header, the triangle count and 50 bytes per triangle
+            if data.len() >= 84 {
+                let count = u32::from_le_bytes([ data[80], data[81], data[82], data[83] ]) as usize;
+
+                if data.len() == 84 + count * 50 {
+                    let mut mesh = Self::default();
+
+                    for tri in data[84 ..].chunks_exact(50) {
+                        let base = mesh.vertices.len() as u32;
+
+                        // Skip the normal (12 bytes), read the three vertices
+                        for v in 0 .. 3 {
+                            let mut vertex = [ 0.0; 3 ];
+                            for (c, value) in vertex.iter_mut().enumerate() {
+                                let i = 12 + v * 12 + c * 4;
+                                *value = f32::from_le_bytes([ tri[i], tri[i + 1], tri[i + 2], tri[i + 3] ]);
+                            }
+                            mesh.vertices.push(vertex);
+                        }
+
+                        mesh.triangles.push([ base, base + 1, base + 2 ]);
+                    }
+
+                    return Ok(mesh);
+                }
+            }
+
+            let text = String::from_utf8_lossy(data);
+
+            if !text.trim_start().starts_with("solid") {
+                return Err("The data is neither a binary nor an ASCII STL file".into());
+            }
+
+            let mut mesh = Self::default();
+
+            for line in text.lines() {
+                let mut parts = line.split_whitespace();
+
+                if parts.next() == Some("vertex") {
+                    mesh.vertices.push(parse_vec(parts)?);
+
+                    if mesh.vertices.len() % 3 == 0 {
+                        let base = mesh.vertices.len() as u32 - 3;
+                        mesh.triangles.push([ base, base + 1, base + 2 ]);
+                    }
+                }
+            }
+
+            if mesh.vertices.len() % 3 != 0 {
+                return Err("The number of vertices is not a multiple of three".into());
+            }
+
+            Ok(mesh)
+        }
+
+        /// Parses an OBJ file, only vertices and faces are read, polygons are split into triangles
+        pub fn parse_obj(text : &str) -> Result<Self, crate::Error> {
+            let mut mesh = Self::default();
+
+            for (line_no, line) in text.lines().enumerate() {
+                let mut parts = line.split_whitespace();
+
+                match parts.next() {
+                    Some("v") => mesh.vertices.push(parse_vec(parts)?),
+                    Some("f") => {
+                        let indices = parts.map(|part| {
+                            let index : i64 = part.split('/').next().unwrap_or("").parse()
+                                .map_err(|_| format!("Bad face index '{}' in line {}", part, line_no + 1))?;
+
+                            // Negative indices are relative to the end of the current vertex list
+                            let index = if index < 0 { mesh.vertices.len() as i64 + index } else { index - 1 };
+
+                            if (index < 0) || (index >= mesh.vertices.len() as i64) {
+                                return Err(format!("Face index '{}' out of range in line {}", part, line_no + 1));
+                            }
+
+                            Ok(index as u32)
+                        }).collect::<Result<Vec<u32>, String>>()?;
+
+                        if indices.len() < 3 {
+                            return Err(format!("Face with less than three vertices in line {}", line_no + 1).into());
+                        }
+
+                        for i in 1 .. (indices.len() - 1) {
+                            mesh.triangles.push([ indices[0], indices[i], indices[i + 1] ]);
+                        }
+                    },
+                    _ => { }
+                }
+            }
+
+            Ok(mesh)
+        }
+
+        /// Scales all vertices by the given factor, the factor is remembered for meshes referring to a file
+        pub fn scale(&mut self, factor : f32) {
+            self.scale *= factor;
+
+            for vertex in self.vertices.iter_mut() {
+                vertex.iter_mut().for_each(|c| *c *= factor);
+            }
+        }
+
+        /// Returns the axis aligned bounding box (min, max) of all vertices
+        pub fn aabb(&self) -> (Vec3, Vec3) {
+            if self.vertices.is_empty() {
+                return (Vec3::ZERO, Vec3::ZERO);
+            }
+
+            self.vertices.iter().fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), v| {
+                (min.min(Vec3::from(*v)), max.max(Vec3::from(*v)))
+            })
+        }
+    }
+
+    fn parse_vec<'a, I : Iterator<Item = &'a str>>(parts : I) -> Result<[f32; 3], crate::Error> {
+        let values = parts.take(3).map(|p| p.parse::<f32>()).collect::<Result<Vec<f32>, _>>()?;
+
+        values.try_into().map_err(|_| "A vertex requires three coordinates".into())
+    }
+//
+
+// Geometry
+    /// What a geometry is used for
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum GeometryUsage {
+        /// Collision checks and visualization
+        #[default]
+        Both,
+        /// Only collision checks
+        Collision,
+        /// Only visualization
+        Visual
+    }
+
+    /// A shape attached to a `WorldObj`
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct Geometry {
+        /// The shape of the geometry
+        pub shape : Shape,
+        /// The pose of the shape relative to the `WorldObj`
+        #[serde(default)]
+        pub pose : Position,
+        /// What the geometry is used for
+        #[serde(default)]
+        pub usage : GeometryUsage
+    }
+
+    impl Geometry {
+        /// Creates a new geometry placed at the origin of the `WorldObj`
+        pub fn new(shape : Shape) -> Self {
+            Self { shape, pose: Position::zero(), usage: GeometryUsage::Both }
+        }
+
+        /// Sets the pose of the geometry relative to its `WorldObj`
+        pub fn with_pose(mut self, pose : Position) -> Self {
+            self.pose = pose;
+            self
+        }
+
+        /// Sets the usage of the geometry
+        pub fn with_usage(mut self, usage : GeometryUsage) -> Self {
+            self.usage = usage;
+            self
+        }
+
+        /// Scales the shape and the position of the geometry by the given factor
+        pub fn scale(&mut self, factor : f32) {
+            self.shape.scale(factor);
+            self.pose.scale(factor);
+        }
+    }
+//
+
+impl WorldObj {
+    /// Attaches the given geometry to the `WorldObj`
+    pub fn add_geometry(&mut self, geometry : Geometry) {
+        self.geometry.push(geometry);
+    }
+
+    /// Builder variant of `add_geometry()`
+    pub fn with_geometry(mut self, geometry : Geometry) -> Self {
+        self.add_geometry(geometry);
+        self
+    }
+
+    /// Loads the data of all meshes in the tree that refer to a file but have no data yet, relative paths are resolved
+    /// with the given base directory. The file paths are kept as written (so saved scenes stay portable), the scale of
+    /// the meshes is applied to the loaded vertices
+    pub fn load_meshes(&mut self, base_dir : &Path) -> Result<(), crate::Error> {
+        for geom in self.geometry.iter_mut() {
+            if let Shape::Mesh(mesh) = &mut geom.shape {
+                if let (Some(file), true) = (mesh.file.clone(), mesh.vertices.is_empty()) {
+                    let scale = mesh.scale;
+                    *mesh = Mesh::load(base_dir.join(&file))?;
+                    mesh.file = Some(file);
+                    mesh.scale(scale);
+                }
+            }
+        }
+
+        for point in self.sub.values() {
+            if let Some(wo) = point.borrow_mut().as_wo_mut() {
+                wo.load_meshes(base_dir)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prefixes the relative file paths of all meshes in the tree with the given directory, used to keep the paths of
+    /// included scenes valid relative to the including scene
+    pub(crate) fn rebase_meshes(&mut self, dir : &Path) {
+        for geom in self.geometry.iter_mut() {
+            if let Shape::Mesh(Mesh { file: Some(file), .. }) = &mut geom.shape {
+                if Path::new(file.as_str()).is_relative() {
+                    *file = dir.join(file.as_str()).to_string_lossy().into_owned();
+                }
+            }
+        }
+
+        for point in self.sub.values() {
+            if let Some(wo) = point.borrow_mut().as_wo_mut() {
+                wo.rebase_meshes(dir);
+            }
+        }
+    }
+}
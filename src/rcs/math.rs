@@ -1,9 +1,9 @@
 use core::f32::consts::PI;
 
-use glam::Vec3;
+use glam::{Mat3, Quat, Vec3};
 use syunit::*;
 
-use crate::rcs::Point;
+use crate::rcs::{Point, Position, Transform};
 
 pub fn sub_phis<const C : usize>(a : [Phi; C], b : [Phi; C]) -> [Delta; C] {
     let mut deltas = [Delta::ZERO; C];
@@ -13,6 +13,29 @@ pub fn sub_phis<const C : usize>(a : [Phi; C], b : [Phi; C]) -> [Delta; C] {
     deltas
 }
 
+/// Appends the pose `next` (given relative to `base`) to the pose `base`
+pub fn chain_poses(base : &Position, next : &Position) -> Position {
+    Transform::from(base).apply_pos(next)
+}
+
+/// Inverts the given pose, so that `chain_poses(pose, invert_pose(pose))` results in the zero pose
+pub fn invert_pose(pose : &Position) -> Position {
+    Transform::from(pose).inverse().to_pos()
+}
+
+/// Normalizes the given angle to the range (-PI, PI]
+pub fn normalize_angle(angle : f32) -> f32 {
+    let mut angle = angle % (2.0 * PI);
+
+    if angle > PI {
+        angle -= 2.0 * PI;
+    } else if angle <= -PI {
+        angle += 2.0 * PI;
+    }
+
+    angle
+}
+
 pub fn law_of_cosines(a : f32, b : f32, c : f32) -> f32 {
     ((a.powi(2) + b.powi(2) - c.powi(2)) / 2.0 / a / b).acos()
 }
@@ -64,6 +87,226 @@ pub fn split_linear(pos_0 : Vec3, delta_pos : Vec3, split_len : f32) -> Vec<Vec3
     pos_vec
 }
 
+/// Solves the linear system `a * x = b` using gaussian elimination with partial pivoting, returns `None` if the
+/// system is singular
+pub fn solve_linear(mut a : Vec<Vec<f64>>, mut b : Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+
+    for col in 0 .. n {
+        let pivot = (col .. n).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+
+        if a[pivot][col].abs() < 1e-15 {
+            return None;
+        }
+
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+
+            if factor != 0.0 {
+                for (value, pivot_value) in row[col ..].iter_mut().zip(&pivot_row[col ..]) {
+                    *value -= factor * pivot_value;
+                }
+                b[col + 1 + offset] -= factor * b[col];
+            }
+        }
+    }
+
+    let mut x = vec![0.0; n];
+
+    for row in (0 .. n).rev() {
+        let sum : f64 = ((row + 1) .. n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+
+    Some(x)
+}
+
+
+// Pose interpolation
+    /// Interpolates between the two poses, the position linearly and the orientation spherically (slerp), `t = 0` 
+    /// results in `a`, `t = 1` in `b`
+    pub fn interpolate_pose(a : &Position, b : &Position, t : f32) -> Position {
+        Position::new_ori(
+            a.pos().lerp(*b.pos(), t),
+            slerp_ori(a.ori(), b.ori(), t)
+        )
+    }
+
+    /// Spherical interpolation between the two orientations
+    pub fn slerp_ori(a : &Mat3, b : &Mat3, t : f32) -> Mat3 {
+        let q_a = Quat::from_mat3(a).normalize();
+        let q_b = Quat::from_mat3(b).normalize();
+
+        Mat3::from_quat(q_a.slerp(q_b, t))
+    }
+
+    /// Returns the angle of the rotation between the two orientations
+    pub fn ori_distance(a : &Mat3, b : &Mat3) -> f32 {
+        Quat::from_mat3(a).normalize().angle_between(Quat::from_mat3(b).normalize())
+    }
+
+    /// Splits the movement between two poses into equal steps, so that no step exceeds `split_len` in distance or 
+    /// `split_angle` in rotation. Both poses are included
+    pub fn split_pose(a : &Position, b : &Position, split_len : f32, split_angle : f32) -> Vec<Position> {
+        let n_len = (a.pos().distance(*b.pos()) / split_len).ceil();
+        let n_angle = (ori_distance(a.ori(), b.ori()) / split_angle).ceil();
+        let n_split = (n_len.max(n_angle) as usize).max(1);
+
+        (0 ..= n_split).map(|i| interpolate_pose(a, b, i as f32 / n_split as f32)).collect()
+    }
+
+    /// A circular arc through three positions, the orientation is interpolated spherically between the start and the
+    /// end pose. If the three positions lie on a line, the arc degenerates to a linear movement
+    #[derive(Clone, Debug)]
+    pub struct PoseArc {
+        start : Position,
+        end : Position,
+        center : Vec3,
+        /// Vectors from the center to the start and perpendicular to it within the plane of the arc
+        u : Vec3,
+        v : Vec3,
+        radius : f32,
+        angle : f32
+    }
+
+    impl PoseArc {
+        /// Creates a new arc from `start` through `via` to `end`
+        pub fn new(start : Position, via : Vec3, end : Position) -> Self {
+            let p1 = *start.pos();
+            let p3 = *end.pos();
+
+            let a = p1 - via;
+            let b = p3 - via;
+            let axb = a.cross(b);
+            let denom = 2.0 * axb.length_squared();
+
+            if denom < 1e-9 {
+                // Collinear points, linear movement
+                return Self { start, end, center: Vec3::ZERO, u: Vec3::ZERO, v: Vec3::ZERO, radius: 0.0, angle: 0.0 };
+            }
+
+            let center = via + (b * a.length_squared() - a * b.length_squared()).cross(axb) / denom;
+            let radius = (p1 - center).length();
+            // Travelling from start over via to end turns counterclockwise around this normal
+            let normal = (via - p1).cross(p3 - via).normalize();
+
+            let u = (p1 - center) / radius;
+            let v = normal.cross(u);
+
+            let angle_of = |p : Vec3| {
+                let d = p - center;
+                let angle = d.dot(v).atan2(d.dot(u));
+                if angle < 0.0 { angle + 2.0 * PI } else { angle }
+            };
+
+            let angle = angle_of(p3);
+
+            Self { start, end, center, u, v, radius, angle }
+        }
+
+        /// Returns the length of the arc
+        pub fn length(&self) -> f32 {
+            if self.radius == 0.0 {
+                self.start.pos().distance(*self.end.pos())
+            } else {
+                self.radius * self.angle
+            }
+        }
+
+        /// Returns the pose at the given parameter, `t = 0` results in the start, `t = 1` in the end pose
+        pub fn pose_at(&self, t : f32) -> Position {
+            if self.radius == 0.0 {
+                return interpolate_pose(&self.start, &self.end, t);
+            }
+
+            let phi = self.angle * t;
+            Position::new_ori(
+                self.center + (self.u * phi.cos() + self.v * phi.sin()) * self.radius,
+                slerp_ori(self.start.ori(), self.end.ori(), t)
+            )
+        }
+    }
+
+    /// Interpolates along a sequence of poses, the parameter is distributed over the segments by their weight. The 
+    /// weight of a segment is its length plus its rotation angle multiplied with `rot_weight`
+    #[derive(Clone, Debug)]
+    pub struct PoseInterpolator {
+        poses : Vec<Position>,
+        /// Cumulative weights at the end of each segment
+        weights : Vec<f32>
+    }
+
+    impl PoseInterpolator {
+        /// Creates a new interpolator, rotations do not add any weight (pure rotations are passed instantly unless all
+        /// segments have zero weight, in which case all segments are weighted equally)
+        pub fn new(poses : Vec<Position>) -> Result<Self, crate::Error> {
+            Self::with_rot_weight(poses, 0.0)
+        }
+
+        /// Creates a new interpolator, rotations are weighted by the given length per radian
+        pub fn with_rot_weight(poses : Vec<Position>, rot_weight : f32) -> Result<Self, crate::Error> {
+            if poses.is_empty() {
+                return Err("An interpolator requires at least one pose".into());
+            }
+
+            let mut weights = Vec::with_capacity(poses.len());
+            let mut total = 0.0;
+
+            for pair in poses.windows(2) {
+                total += pair[0].pos().distance(*pair[1].pos()) + rot_weight * ori_distance(pair[0].ori(), pair[1].ori());
+                weights.push(total);
+            }
+
+            if total <= 0.0 {
+                for (i, weight) in weights.iter_mut().enumerate() {
+                    *weight = (i + 1) as f32;
+                }
+            }
+
+            Ok(Self { poses, weights })
+        }
+
+        /// Returns the poses of the interpolator
+        pub fn poses(&self) -> &[Position] {
+            &self.poses
+        }
+
+        /// Returns the total weight (the path length if `rot_weight` is zero)
+        pub fn total_weight(&self) -> f32 {
+            self.weights.last().copied().unwrap_or(0.0)
+        }
+
+        /// Returns the pose at the given parameter, `t = 0` results in the first, `t = 1` in the last pose
+        pub fn pose_at(&self, t : f32) -> Position {
+            let target = t.clamp(0.0, 1.0) * self.total_weight();
+            let mut start = 0.0;
+
+            for (i, end) in self.weights.iter().enumerate() {
+                if (target <= *end) && (*end > start) {
+                    return interpolate_pose(&self.poses[i], &self.poses[i + 1], (target - start) / (end - start));
+                }
+
+                start = *end;
+            }
+
+            self.poses[self.poses.len() - 1].clone()
+        }
+
+        /// Samples the path with the given number of equally spaced parameters (at least two, first and last pose 
+        /// included)
+        pub fn sample(&self, count : usize) -> Vec<Position> {
+            let count = count.max(2);
+            (0 .. count).map(|i| self.pose_at(i as f32 / (count - 1) as f32)).collect()
+        }
+    }
+//
+
 // pub fn convert_to_phis<const C : usize>(robot : &impl ActRobot<C>, deco : &[f32], pos_vec : &Vec<Vec3>) 
 // -> Result<Vec<[Phi; C]>, crate::Error> {
 //     let mut phis_vec = vec![]; 
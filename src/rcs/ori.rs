@@ -0,0 +1,137 @@
+use glam::{Mat3, Vec3};
+use serde::{Serialize, Deserialize};
+
+/// Rotation sequences of euler angles (Tait-Bryan angles). The sequences are intrinsic, meaning `XYZ` results in the
+/// rotation matrix `Rx(a) * Ry(b) * Rz(c)`, the rotations are applied around the already rotated axes
+///
+/// The extrinsic sequence (fixed axes) equals the intrinsic sequence in reversed order, e.g. roll-pitch-yaw around the
+/// fixed X, Y and Z axes equals the intrinsic sequence `ZYX` with the angles (yaw, pitch, roll)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EulerOrder {
+    XYZ,
+    XZY,
+    YXZ,
+    YZX,
+    ZXY,
+    ZYX
+}
+
+impl EulerOrder {
+    /// Indices of the three axes of the sequence
+    fn axes(self) -> (usize, usize, usize) {
+        match self {
+            Self::XYZ => (0, 1, 2),
+            Self::XZY => (0, 2, 1),
+            Self::YXZ => (1, 0, 2),
+            Self::YZX => (1, 2, 0),
+            Self::ZXY => (2, 0, 1),
+            Self::ZYX => (2, 1, 0)
+        }
+    }
+
+    /// Creates the rotation matrix out of the given angles
+    pub fn to_mat3(self, angles : [f32; 3]) -> Mat3 {
+        let (i, j, k) = self.axes();
+        axis_rotation(i, angles[0]) * axis_rotation(j, angles[1]) * axis_rotation(k, angles[2])
+    }
+
+    /// Decomposes the given rotation matrix into the angles of the sequence. The middle angle lies in the range
+    /// [-PI/2, PI/2], at the singularity (middle angle of ±PI/2) the last angle is set to zero
+    pub fn angles(self, ori : &Mat3) -> [f32; 3] {
+        let (i, j, k) = self.axes();
+        let m = |row : usize, col : usize| ori.col(col)[row];
+
+        // Cyclic sequences (XYZ, YZX, ZXY) have a positive parity
+        let s = if (j + 3 - i) % 3 == 1 { 1.0 } else { -1.0 };
+        let sin_b = (s * m(i, k)).clamp(-1.0, 1.0);
+
+        if sin_b.abs() > 0.99999 {
+            [ (s * m(k, j)).atan2(m(j, j)), sin_b.asin(), 0.0 ]
+        } else {
+            [
+                (-s * m(j, k)).atan2(m(k, k)),
+                sin_b.asin(),
+                (-s * m(i, j)).atan2(m(i, i))
+            ]
+        }
+    }
+}
+
+fn axis_rotation(axis : usize, angle : f32) -> Mat3 {
+    match axis {
+        0 => Mat3::from_rotation_x(angle),
+        1 => Mat3::from_rotation_y(angle),
+        _ => Mat3::from_rotation_z(angle)
+    }
+}
+
+/// Creates the rotation matrix of the given roll (X), pitch (Y) and yaw (Z) angles around the fixed axes
+#[inline]
+pub fn rpy_to_mat3(roll : f32, pitch : f32, yaw : f32) -> Mat3 {
+    EulerOrder::ZYX.to_mat3([ yaw, pitch, roll ])
+}
+
+/// Decomposes the rotation matrix into roll (X), pitch (Y) and yaw (Z) angles around the fixed axes
+#[inline]
+pub fn mat3_to_rpy(ori : &Mat3) -> [f32; 3] {
+    let [ yaw, pitch, roll ] = EulerOrder::ZYX.angles(ori);
+    [ roll, pitch, yaw ]
+}
+
+/// Human-editable representations of an orientation, used for the serialization of positions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum OriDes {
+    /// Column-major rotation matrix
+    Matrix([[f32; 3]; 3]),
+    /// Euler angles with the given sequence
+    Euler {
+        euler : [f32; 3],
+        order : EulerOrder,
+        #[serde(default)]
+        degrees : bool
+    },
+    /// Roll, pitch and yaw angles around the fixed axes
+    Rpy {
+        rpy : [f32; 3],
+        #[serde(default)]
+        degrees : bool
+    },
+    /// Quaternion in the order x, y, z, w
+    Quat {
+        quat : [f32; 4]
+    },
+    /// Rotation by `angle` around `axis`
+    AxisAngle {
+        axis : [f32; 3],
+        angle : f32,
+        #[serde(default)]
+        degrees : bool
+    }
+}
+
+impl From<&Mat3> for OriDes {
+    fn from(ori : &Mat3) -> Self {
+        Self::Matrix(ori.to_cols_array_2d())
+    }
+}
+
+impl From<&OriDes> for Mat3 {
+    fn from(ori : &OriDes) -> Self {
+        let to_rad = |v : f32, degrees : bool| if degrees { v.to_radians() } else { v };
+
+        match ori {
+            OriDes::Matrix(cols) => Mat3::from_cols_array_2d(cols),
+            OriDes::Euler { euler, order, degrees } =>
+                order.to_mat3(euler.map(|v| to_rad(v, *degrees))),
+            OriDes::Rpy { rpy, degrees } => {
+                let [ roll, pitch, yaw ] = rpy.map(|v| to_rad(v, *degrees));
+                rpy_to_mat3(roll, pitch, yaw)
+            },
+            OriDes::Quat { quat } =>
+                Mat3::from_quat(glam::Quat::from_array(*quat).normalize()),
+            OriDes::AxisAngle { axis, angle, degrees } =>
+                Mat3::from_axis_angle(Vec3::from(*axis).normalize(), to_rad(*angle, *degrees))
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::rcs::{LengthUnit, WorldObj};
+
+/// The current version of the scene file format (version 2 added the `unit` field, version 1 files use millimetres)
+pub const SCENE_VERSION : u32 = 2;
+
+/// The content of a scene file
+///
+/// ```json
+/// {
+///     "version": 2,
+///     "unit": "mm",
+///     "root": {
+///         "pos": { "pos": [ 0, 0, 0 ] },
+///         "sub": {
+///             "pallet1": { "include": "fixtures/pallet1.json", "pos": { "pos": [ 500, 0, 0 ] } }
+///         }
+///     }
+/// }
+/// ```
+///
+/// Any point in the tree can be replaced by an `include` of another scene file (path relative to the including file),
+/// the optional `pos` replaces the pose of the root of the included scene. Includes are resolved when loading, saving
+/// a tree writes the included scenes inline (see `WorldObj::save()`). All lengths of a file (including the `pos` 
+/// of includes) are given in the unit of the file (`"mm"`, `"m"` or `"in"`), included scenes are converted 
+/// automatically
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SceneFile {
+    /// Version of the file format
+    pub version : u32,
+    /// The unit of all lengths in the file
+    #[serde(default)]
+    pub unit : LengthUnit,
+    /// The root of the scene
+    pub root : WorldObj
+}
+
+impl WorldObj {
+    /// Loads a scene file (see `SceneFile`), resolving all includes. The tree is converted into millimetres
+    pub fn load<P : AsRef<Path>>(path : P) -> Result<Self, crate::Error> {
+        let mut wobj = load_scene(path.as_ref(), &mut Vec::new())?;
+        wobj.convert_unit(LengthUnit::Millimetre);
+        Ok(wobj)
+    }
+
+    /// Saves the tree as scene file in the unit of the tree
+    /// 
+    /// The tree does not remember which parts have been included from other files, all included scenes are therefore
+    /// written inline (a load/save round-trip flattens the file structure into a single file, the included files are 
+    /// left untouched). Relative mesh paths are written as loaded, relative to the loaded scene file
+    pub fn save<P : AsRef<Path>>(&self, path : P) -> Result<(), crate::Error> {
+        let scene = SceneFile { version: SCENE_VERSION, unit: self.unit(), root: self.clone() };
+        let content = serde_json::to_string_pretty(&scene)?;
+
+        fs::write(path.as_ref(), content)
+            .map_err(|err| format!("Failed to write scene file '{}': {}", path.as_ref().display(), err).into())
+    }
+}
+
+/// Loads the scene file at the given path, the tree is returned in the unit declared by the file
+fn load_scene(path : &Path, stack : &mut Vec<PathBuf>) -> Result<WorldObj, crate::Error> {
+    let canonical = fs::canonicalize(path)
+        .map_err(|err| format!("Failed to open scene file '{}': {}", path.display(), err))?;
+
+    if stack.contains(&canonical) {
+        return Err(format!("The scene file '{}' includes itself", path.display()).into());
+    }
+
+    let content = fs::read_to_string(&canonical)
+        .map_err(|err| format!("Failed to read scene file '{}': {}", path.display(), err))?;
+
+    let mut value : Value = serde_json::from_str(&content)
+        .map_err(|err| format!("Failed to parse scene file '{}': {}", path.display(), err))?;
+
+    match value.get("version").and_then(Value::as_u64) {
+        Some(version) if (1 ..= SCENE_VERSION as u64).contains(&version) => { },
+        Some(version) => return Err(format!("The scene file '{}' has version {}, the supported versions are 1 to {}",
+            path.display(), version, SCENE_VERSION).into()),
+        None => return Err(format!("The scene file '{}' has no valid version header", path.display()).into())
+    }
+
+    let unit : LengthUnit = match value.get("unit") {
+        Some(unit) => serde_json::from_value(unit.clone())
+            .map_err(|err| format!("Bad unit in scene file '{}': {}", path.display(), err))?,
+        None => LengthUnit::default()
+    };
+
+    stack.push(canonical.clone());
+
+    let base_dir = canonical.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    if let Some(root) = value.get_mut("root") {
+        resolve_includes(root, &base_dir, stack, unit)
+            .map_err(|err| format!("In scene file '{}': {}", path.display(), err))?;
+    }
+
+    stack.pop();
+
+    let mut scene : SceneFile = serde_path_to_error::deserialize(value)
+        .map_err(|err| format!("Bad scene file '{}' at '{}': {}", path.display(), err.path(), err.inner()))?;
+
+    scene.root.set_unit(scene.unit);
+
+    // Mesh files are relative to the scene file, loaded meshes store their full path
+    scene.root.load_meshes(&base_dir)
+        .map_err(|err| format!("In scene file '{}': {}", path.display(), err))?;
+
+    Ok(scene.root)
+}
+
+/// Replaces all include entries in the sub-points of the given point, the included scenes are converted into `unit`
+fn resolve_includes(point : &mut Value, base_dir : &Path, stack : &mut Vec<PathBuf>, unit : LengthUnit) -> Result<(), crate::Error> {
+    let Some(sub) = point.get_mut("sub").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+
+    for child in sub.values_mut() {
+        let include = child.get("include").and_then(Value::as_str).map(str::to_owned);
+
+        if let Some(include) = include {
+            let mut wobj = load_scene(&base_dir.join(&include), stack)?;
+            wobj.convert_unit(unit);
+
+            if let Some(include_dir) = Path::new(&include).parent() {
+                wobj.rebase_meshes(include_dir);
+            }
+
+            if let Some(pos) = child.get("pos") {
+                let pose = serde_path_to_error::deserialize(pos.clone())
+                    .map_err(|err| format!("Bad pose of the include '{}' at '{}': {}", include, err.path(), err.inner()))?;
+                wobj.pos = pose;
+            }
+
+            *child = serde_json::to_value(&wobj)?;
+        } else {
+            resolve_includes(child, base_dir, stack, unit)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use core::ops::Mul;
+
+use glam::{Mat3, Vec3};
+
+use crate::rcs::{Point, Position};
+
+/// A rigid transformation, consisting of a rotation followed by a translation. Applied to a vector `v` it results in
+/// `rot * v + trans`
+///
+/// Every pose (`Position`, `WorldObj`, ...) describes the transformation from its own coordinates into the coordinates
+/// of its parent, `Transform::from_pose()` creates this transformation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    /// The rotation part
+    pub rot : Mat3,
+    /// The translation part
+    pub trans : Vec3
+}
+
+impl Transform {
+    /// The identity transformation
+    pub const IDENTITY : Self = Self { rot: Mat3::IDENTITY, trans: Vec3::ZERO };
+
+    /// Creates a new transformation out of a rotation and a translation
+    pub fn new(rot : Mat3, trans : Vec3) -> Self {
+        Self { rot, trans }
+    }
+
+    /// Creates a pure translation
+    pub fn from_translation(trans : Vec3) -> Self {
+        Self { rot: Mat3::IDENTITY, trans }
+    }
+
+    /// Creates a pure rotation around the origin
+    pub fn from_rotation(rot : Mat3) -> Self {
+        Self { rot, trans: Vec3::ZERO }
+    }
+
+    /// Creates a rotation around the point `pivot`
+    pub fn from_rotation_about(rot : Mat3, pivot : Vec3) -> Self {
+        Self { rot, trans: pivot - rot * pivot }
+    }
+
+    /// Creates the transformation from the coordinates of the given point into the coordinates of its parent
+    pub fn from_pose<P : Point + ?Sized>(point : &P) -> Self {
+        Self { rot: *point.ori(), trans: *point.pos() }
+    }
+
+    /// Converts the transformation into a pose
+    pub fn to_pos(&self) -> Position {
+        Position::new_ori(self.trans, self.rot)
+    }
+
+    /// Composes the two transformations, the result applies `other` first and `self` afterwards
+    pub fn compose(&self, other : &Transform) -> Self {
+        Self {
+            rot: self.rot * other.rot,
+            trans: self.rot * other.trans + self.trans
+        }
+    }
+
+    /// Returns the inverse transformation, the rotation part is expected to be orthonormal
+    pub fn inverse(&self) -> Self {
+        let rot_inv = self.rot.transpose();
+        Self { rot: rot_inv, trans: -(rot_inv * self.trans) }
+    }
+
+    /// Applies the transformation to the given point coordinates
+    #[inline]
+    pub fn apply_vec(&self, v : Vec3) -> Vec3 {
+        self.rot * v + self.trans
+    }
+
+    /// Applies only the rotation part to the given direction
+    #[inline]
+    pub fn apply_dir(&self, dir : Vec3) -> Vec3 {
+        self.rot * dir
+    }
+
+    /// Applies the transformation to the given pose, both its position and orientation are transformed
+    pub fn apply_pos(&self, pos : &Position) -> Position {
+        Position::new_ori(self.apply_vec(*pos.pos()), self.rot * *pos.ori())
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs : Transform) -> Self::Output {
+        self.compose(&rhs)
+    }
+}
+
+impl Mul<Vec3> for Transform {
+    type Output = Vec3;
+
+    fn mul(self, rhs : Vec3) -> Self::Output {
+        self.apply_vec(rhs)
+    }
+}
+
+impl From<&Position> for Transform {
+    fn from(pos : &Position) -> Self {
+        Self::from_pose(pos)
+    }
+}
+
+impl From<Transform> for Position {
+    fn from(tf : Transform) -> Self {
+        tf.to_pos()
+    }
+}
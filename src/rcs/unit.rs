@@ -0,0 +1,94 @@
+use core::fmt::Display;
+
+use serde::{Serialize, Deserialize};
+
+use crate::rcs::{Point, WorldObj};
+
+/// The unit of all lengths in a `WorldObj` tree or a file, the library itself works in millimetres by default
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LengthUnit {
+    /// Millimetres (default)
+    #[default]
+    #[serde(rename = "mm", alias = "millimetre")]
+    Millimetre,
+    /// Metres, used by URDF files
+    #[serde(rename = "m", alias = "metre")]
+    Metre,
+    /// Inches
+    #[serde(rename = "in", alias = "inch")]
+    Inch
+}
+
+impl LengthUnit {
+    /// The length of one unit in millimetres
+    pub fn in_mm(self) -> f32 {
+        match self {
+            Self::Millimetre => 1.0,
+            Self::Metre => 1000.0,
+            Self::Inch => 25.4
+        }
+    }
+
+    /// The factor required to convert a length from this unit into the unit `to`
+    pub fn factor_to(self, to : LengthUnit) -> f32 {
+        self.in_mm() / to.in_mm()
+    }
+
+    /// Converts the given length from this unit into the unit `to`
+    pub fn convert(self, value : f32, to : LengthUnit) -> f32 {
+        if self == to {
+            value
+        } else {
+            value * self.factor_to(to)
+        }
+    }
+}
+
+impl Display for LengthUnit {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Millimetre => "mm",
+            Self::Metre => "m",
+            Self::Inch => "in"
+        })
+    }
+}
+
+impl WorldObj {
+    /// The unit of all lengths in this `WorldObj` and its sub-points
+    #[inline]
+    pub fn unit(&self) -> LengthUnit {
+        self.unit
+    }
+
+    /// Converts all lengths of the tree (positions and geometry) into the given unit
+    pub fn convert_unit(&mut self, unit : LengthUnit) {
+        if self.unit != unit {
+            self.scale(self.unit.factor_to(unit));
+            self.set_unit(unit);
+        }
+    }
+
+    /// Declares the unit of the tree without converting any values, used when the unit is only known after the values 
+    /// have been created (e.g. while loading files)
+    pub(crate) fn set_unit(&mut self, unit : LengthUnit) {
+        self.unit = unit;
+
+        for point in self.sub.values() {
+            if let Some(wo) = point.borrow_mut().as_wo_mut() {
+                wo.set_unit(unit);
+            }
+        }
+    }
+
+    /// Checks that a point can be added to this tree without mixing up length units
+    pub(crate) fn check_unit(&self, name : &str, point : &dyn Point) -> Result<(), crate::Error> {
+        match point.as_wo() {
+            Some(wo) if wo.unit != self.unit => Err(format!(
+                "The point '{}' uses the length unit '{}', but the system uses '{}'! Use `convert_unit()` first", 
+                name, wo.unit, self.unit
+            ).into()),
+            _ => Ok(())
+        }
+    }
+}
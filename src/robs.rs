@@ -9,7 +9,8 @@ use tokio::task::JoinSet;
 // use crate::pkg::info::AngConf;
 use crate::{Descriptor, PushRemote};
 use crate::config::AngleConfig;
-use crate::rcs::Position;
+use crate::rcs::FramedPosition;
+use crate::teach::PositionLibrary;
 
 // ####################
 // #    SUBMODULES    #
@@ -159,8 +160,10 @@ pub trait Robot<G : SyncActuatorGroup<T, C>, T : SyncActuator + DefinedActuator
             Ok(())
         }
 
-        async fn move_p_sync<D : Descriptor<C>>(&mut self, desc : &mut D, p : Position, speed_f : Factor) -> Result<(), crate::Error> {
-            let phis = desc.phis_for_pos(p)?;
+        async fn move_p_sync<D : Descriptor<C>, P : Into<FramedPosition>>(&mut self, desc : &mut D, p : P, speed_f : Factor) 
+        -> Result<(), crate::Error> {
+            desc.set_tool(self.get_tool());
+            let phis = desc.phis_for_framed(&p.into())?;
             self.move_abs_j_sync(
                 phis,
                 speed_f
@@ -203,9 +206,28 @@ pub trait Robot<G : SyncActuatorGroup<T, C>, T : SyncActuator + DefinedActuator
             self.move_l(desc, pos - pos_0, accuracy, speed).await
         }
 
-        async fn move_p<D : Descriptor<C>>(&mut self, desc: &mut D, p : Position, speed_f : Factor) -> Result<(), crate::Error>
+        async fn move_p<D : Descriptor<C>, P : Into<FramedPosition>>(&mut self, desc: &mut D, p : P, speed_f : Factor) 
+        -> Result<(), crate::Error>
         where Self: Sized {
-            let phis = desc.phis_for_pos(p)?;
+            desc.set_tool(self.get_tool());
+            let phis = desc.phis_for_framed(&p.into())?;
+            self.move_abs_j(
+                phis,
+                speed_f
+            ).await
+        }
+
+        /// Moves to the position with the given name out of the library (see `PositionLibrary::phis()`), the tool of
+        /// the robot is applied to the descriptor first
+        async fn move_to_named<D, A>(&mut self, desc : &mut D, lib : &PositionLibrary<A>, name : &str, speed_f : Factor) 
+        -> Result<(), crate::Error>
+        where 
+            Self: Sized,
+            D : Descriptor<C, AxisConfig = A>,
+            A : Clone
+        {
+            desc.set_tool(self.get_tool());
+            let phis = lib.phis(name, desc)?;
             self.move_abs_j(
                 phis,
                 speed_f
@@ -244,8 +266,19 @@ pub trait Robot<G : SyncActuatorGroup<T, C>, T : SyncActuator + DefinedActuator
         fn get_tools(&self) -> &Vec<Box<dyn Tool>>;
 
         /// Sets the id of the tool to be used and performs an automatic tool swap if necessary
+        /// 
+        /// Descriptors apply the new tool to their TCP with the next movement (`move_p`) or call to `Descriptor::update()`,
+        /// use `switch_tool()` to apply it to a descriptor right away
         fn set_tool_id(&mut self, tool_id : Option<usize>) -> Option<&mut dyn Tool>;
 
+        /// Sets the id of the tool to be used (see `set_tool_id()`) and applies the tool to the given descriptor, so its
+        /// TCP is placed at the tip of the new tool
+        fn switch_tool<D : Descriptor<C>>(&mut self, desc : &mut D, tool_id : Option<usize>) 
+        where Self: Sized {
+            self.set_tool_id(tool_id);
+            desc.set_tool(self.get_tool());
+        }
+
         // Wrapper functions
             fn activate_tool(&mut self) -> Result<&dyn tool::SimpleTool, crate::Error> {
                 let tool = self.get_tool_mut()
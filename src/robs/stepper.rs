@@ -38,8 +38,25 @@ where
     /// - `ang_confs`: A given set of angle configurations, to convert phi into gamma values
     /// - `comps`: The components of the robot, must be a `StepperActuatorGroup`
     /// - `tools`: The set of tools equipped by the robot
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if one of the angle configurations is invalid (see `AngleConfig::validate()`), use `try_new()` to 
+    /// handle the error instead
     pub fn new(ang_confs : [AngleConfig; C], comps : G, tools : Vec<Box<dyn Tool>>) -> Self {
-        Self {
+        match Self::try_new(ang_confs, comps, tools) {
+            Ok(robot) => robot,
+            Err(err) => panic!("{}", err)
+        }
+    }
+
+    /// Creates a new stepper robot like `new()`, returns an error if one of the angle configurations is invalid
+    pub fn try_new(ang_confs : [AngleConfig; C], comps : G, tools : Vec<Box<dyn Tool>>) -> Result<Self, crate::Error> {
+        for (i, conf) in ang_confs.iter().enumerate() {
+            conf.validate().map_err(|err| format!("Bad angle configuration of the component {}: {}", i, err))?;
+        }
+
+        Ok(Self {
             _vars: Vars::default(),
 
             _ang_confs: ang_confs,
@@ -51,7 +68,7 @@ where
             remotes: Vec::new(),
 
             __pd : PhantomData::default()
-        }
+        })
     }
 }
 
@@ -5,6 +5,8 @@ use glam::Vec3;
 use syact::{Dismantle, Setup};
 use syunit::*;
 
+use crate::rcs::Position;
+
 // // Tools
 //     mod axial_joint;
 //     pub use axial_joint::AxialJoint;
@@ -66,6 +68,12 @@ pub trait Tool : Setup + Dismantle {
         /// Returns the characteristic vector of the tool
         fn vec(&self) -> Vec3;
 
+        /// Returns the pose of the tool center point relative to the mounting point of the tool, by default the 
+        /// characteristic vector without any rotation
+        fn tcp(&self) -> Position {
+            Position::from(self.vec())
+        }
+
         /// Returns the tool inhertia 
         fn inertia(&self) -> Inertia;
 
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use glam::{Mat3, Vec3};
+use syunit::*;
+
+use crate::Descriptor;
+use crate::desc::Kinematic;
+use crate::rcs::{FramedPosition, Point, PointRef, Position, WorldObj};
+use crate::rcs::math::{chain_poses, invert_pose};
+
+/// Maximum difference between the taught and the current tool (length and orientation matrix entries)
+const TOOL_TOLERANCE : f32 = 1e-4;
+
+// Taught positions
+    /// A taught position, stored as Cartesian position and/or as joint values, together with the tool and the axis
+    /// configuration active when it was taught
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(bound(serialize = "A : Serialize", deserialize = "A : DeserializeOwned"))]
+    pub struct TaughtPosition<A> {
+        /// The position of the TCP, relative to a frame of the `WorldObj` tree
+        #[serde(default)]
+        pub pos : Option<FramedPosition>,
+        /// The joint values
+        #[serde(default)]
+        pub phis : Option<Vec<Phi>>,
+        /// The TCP of the tool active when the position was taught (relative to the flange)
+        #[serde(default)]
+        pub tool : Option<Position>,
+        /// The axis configuration active when the position was taught
+        #[serde(default)]
+        pub axis_config : Option<A>
+    }
+
+    impl<A> TaughtPosition<A> {
+        /// Creates a new Cartesian position without tool and axis configuration
+        pub fn cartesian<P : Into<FramedPosition>>(pos : P) -> Self {
+            Self { pos: Some(pos.into()), phis: None, tool: None, axis_config: None }
+        }
+
+        /// Creates a new joint position
+        pub fn joints<const C : usize>(phis : [Phi; C]) -> Self {
+            Self { pos: None, phis: Some(phis.to_vec()), tool: None, axis_config: None }
+        }
+    }
+//
+
+/// A library of named taught positions, which can be saved and loaded as JSON
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "A : Serialize", deserialize = "A : DeserializeOwned"))]
+pub struct PositionLibrary<A> {
+    positions : BTreeMap<String, TaughtPosition<A>>
+}
+
+impl<A> Default for PositionLibrary<A> {
+    fn default() -> Self {
+        Self { positions: BTreeMap::new() }
+    }
+}
+
+impl<A> PositionLibrary<A> {
+    /// Creates a new empty library
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Access
+        /// Inserts the position with the given name, returns the previous position with the same name
+        pub fn insert<N : Into<String>>(&mut self, name : N, pos : TaughtPosition<A>) -> Option<TaughtPosition<A>> {
+            self.positions.insert(name.into(), pos)
+        }
+
+        /// Removes the position with the given name
+        pub fn remove(&mut self, name : &str) -> Option<TaughtPosition<A>> {
+            self.positions.remove(name)
+        }
+
+        /// Returns the position with the given name
+        pub fn get(&self, name : &str) -> Option<&TaughtPosition<A>> {
+            self.positions.get(name)
+        }
+
+        /// Returns the position with the given name or an error if it does not exist
+        pub fn req(&self, name : &str) -> Result<&TaughtPosition<A>, crate::Error> {
+            self.get(name).ok_or_else(|| format!("The library has no position named '{}'", name).into())
+        }
+
+        /// Iterates over all positions, sorted by their names
+        pub fn iter(&self) -> impl Iterator<Item = (&String, &TaughtPosition<A>)> {
+            self.positions.iter()
+        }
+
+        /// Number of positions in the library
+        pub fn len(&self) -> usize {
+            self.positions.len()
+        }
+
+        /// Returns `true` if the library contains no positions
+        pub fn is_empty(&self) -> bool {
+            self.positions.is_empty()
+        }
+    //
+
+    // Teaching
+        /// Teaches the current position of the descriptor under the given name. The pose of the tool tip (see 
+        /// `Kinematic::calculate_end()`) is stored relative to the given frame of the `WorldObj` tree, together with the
+        /// current joint values, tool and axis configuration
+        pub fn teach<const C : usize, D, N>(&mut self, name : N, desc : &D, frame : Option<&str>) -> Result<(), crate::Error>
+        where
+            D : Descriptor<C, AxisConfig = A>,
+            A : Clone,
+            N : Into<String>
+        {
+            let tcp = desc.kinematic().calculate_end();
+
+            let pos = if let Some(frame) = frame {
+                let frame_pose = desc.world_obj().relative_pose("", frame)
+                    .ok_or_else(|| format!("The frame '{}' does not exist", frame))?;
+
+                FramedPosition::new(frame, chain_poses(&invert_pose(&frame_pose), &tcp))
+            } else {
+                FramedPosition::base(tcp)
+            };
+
+            self.insert(name, TaughtPosition {
+                pos: Some(pos),
+                phis: Some(desc.kinematic().phis().to_vec()),
+                tool: Some(desc.kinematic().tool().clone()),
+                axis_config: Some(desc.axis_config().clone())
+            });
+
+            Ok(())
+        }
+
+        /// Returns the joint values to reach the position with the given name. Cartesian positions are preferred, so
+        /// moved or re-taught frames are respected, the stored axis configuration is applied to the descriptor before
+        /// the calculation. 
+        /// 
+        /// Cartesian positions taught with a tool can only be recalled with the same tool applied to the descriptor, 
+        /// as the TCP would end up at a different pose otherwise, an error is returned if the tools differ
+        pub fn phis<const C : usize, D>(&self, name : &str, desc : &mut D) -> Result<[Phi; C], crate::Error>
+        where
+            D : Descriptor<C, AxisConfig = A>,
+            A : Clone
+        {
+            let taught = self.req(name)?;
+
+            if let Some(pos) = &taught.pos {
+                if let Some(tool) = &taught.tool {
+                    let current = desc.kinematic().tool();
+
+                    if ((*tool.pos() - *current.pos()).length() > TOOL_TOLERANCE) 
+                        || !tool.ori().abs_diff_eq(*current.ori(), TOOL_TOLERANCE) {
+                        return Err(format!("The position '{}' has been taught with a different tool ({:?}) than the one \
+                            currently applied ({:?})", name, tool, current).into());
+                    }
+                }
+
+                if let Some(conf) = &taught.axis_config {
+                    *desc.axis_config_mut() = conf.clone();
+                }
+
+                desc.phis_for_framed(pos)
+            } else if let Some(phis) = &taught.phis {
+                phis.as_slice().try_into().map_err(|_|
+                    format!("The position '{}' has {} joint values, {} are required", name, phis.len(), C).into()
+                )
+            } else {
+                Err(format!("The position '{}' has neither a Cartesian position nor joint values", name).into())
+            }
+        }
+    //
+
+    // Persistence
+        /// Loads a library out of a JSON file
+        pub fn load<P : AsRef<Path>>(path : P) -> Result<Self, crate::Error>
+        where
+            A : DeserializeOwned
+        {
+            let path = path.as_ref();
+            let content = fs::read_to_string(path)
+                .map_err(|err| format!("Failed to read position library '{}': {}", path.display(), err))?;
+
+            let de = &mut serde_json::Deserializer::from_str(&content);
+            serde_path_to_error::deserialize(de)
+                .map_err(|err| format!("Bad position library '{}' at '{}': {}", path.display(), err.path(), err.inner()).into())
+        }
+
+        /// Saves the library as JSON file
+        pub fn save<P : AsRef<Path>>(&self, path : P) -> Result<(), crate::Error>
+        where
+            A : Serialize
+        {
+            let path = path.as_ref();
+            fs::write(path, serde_json::to_string_pretty(self)?)
+                .map_err(|err| format!("Failed to write position library '{}': {}", path.display(), err).into())
+        }
+    //
+}
+
+// Frames
+    /// Minimum distance between the taught points of a frame
+    const FRAME_POINT_TOLERANCE : f32 = 1e-3;
+
+    /// A user or work-object frame taught with three points
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct TaughtFrame {
+        /// The pose of the frame in the coordinates the points have been taught in
+        pub pose : Position,
+        /// Absolute deviation of the angle between the X-axis and the direction towards the XY-plane point from 90° 
+        /// (in radians), zero if the XY-plane point lies exactly on the Y-axis
+        pub ortho_error : f32
+    }
+
+    impl TaughtFrame {
+        /// Creates a frame out of its origin, a point on the +X axis and a point in the XY-plane (on the +Y side).
+        /// The X-axis points exactly from the origin to `x_point`, the Y-axis is made orthogonal to it
+        pub fn from_points(origin : Vec3, x_point : Vec3, xy_point : Vec3) -> Result<Self, crate::Error> {
+            let x_dir = x_point - origin;
+            let xy_dir = xy_point - origin;
+
+            if x_dir.length() < FRAME_POINT_TOLERANCE {
+                return Err("The point on the X-axis must differ from the origin".into());
+            }
+
+            if xy_dir.length() < FRAME_POINT_TOLERANCE {
+                return Err("The point in the XY-plane must differ from the origin".into());
+            }
+
+            let x_axis = x_dir.normalize();
+            let z_dir = x_axis.cross(xy_dir);
+
+            if z_dir.length() < FRAME_POINT_TOLERANCE {
+                return Err("The three points of a frame must not lie on a line".into());
+            }
+
+            let z_axis = z_dir.normalize();
+            let y_axis = z_axis.cross(x_axis);
+
+            Ok(Self {
+                pose: Position::new_ori(origin, Mat3::from_cols(x_axis, y_axis, z_axis)),
+                ortho_error: (x_axis.angle_between(xy_dir.normalize()) - core::f32::consts::FRAC_PI_2).abs()
+            })
+        }
+
+        /// Inserts the frame into the tree at the given path, the points must have been taught in the coordinates of
+        /// the root of the tree. If a point exists at the path already (re-teaching), only its pose is updated, so 
+        /// all points attached to it move with the frame
+        pub fn insert(&self, wobj : &mut WorldObj, path : &str) -> Result<(), crate::Error> {
+            let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+
+            let parent_pose = wobj.relative_pose("", parent)
+                .ok_or_else(|| format!("The parent '{}' of the frame does not exist", parent))?;
+            let pose = chain_poses(&invert_pose(&parent_pose), &self.pose);
+
+            if let Some(point) = wobj.point(path) {
+                let mut p = point.borrow_mut();
+                *p.pos_mut() = *pose.pos();
+                *p.ori_mut() = *pose.ori();
+                return Ok(());
+            }
+
+            let frame = PointRef::new(WorldObj::from_pos(pose));
+
+            if parent.is_empty() {
+                return wobj.add_point(name, frame);
+            }
+
+            let parent_ref = wobj.req_point(parent)?;
+            let mut p = parent_ref.borrow_mut();
+
+            match p.as_wo_mut() {
+                Some(wo) => wo.add_point(name, frame),
+                None => Err(format!("The parent '{}' of the frame is not a world object", parent).into())
+            }
+        }
+    }
+
+    /// Collects the three TCP readings required to teach a frame, see `TaughtFrame::from_points()`
+    #[derive(Clone, Debug, Default)]
+    pub struct FrameTeacher {
+        /// The origin of the frame
+        pub origin : Option<Vec3>,
+        /// A point on the +X axis
+        pub x_point : Option<Vec3>,
+        /// A point in the XY-plane
+        pub xy_point : Option<Vec3>
+    }
+
+    impl FrameTeacher {
+        /// Creates a new teacher without any points
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Records the current tool tip as origin of the frame
+        pub fn teach_origin<const C : usize, D : Descriptor<C>>(&mut self, desc : &D) {
+            self.origin = Some(*desc.kinematic().calculate_end().pos());
+        }
+
+        /// Records the current tool tip as point on the +X axis
+        pub fn teach_x_point<const C : usize, D : Descriptor<C>>(&mut self, desc : &D) {
+            self.x_point = Some(*desc.kinematic().calculate_end().pos());
+        }
+
+        /// Records the current tool tip as point in the XY-plane
+        pub fn teach_xy_point<const C : usize, D : Descriptor<C>>(&mut self, desc : &D) {
+            self.xy_point = Some(*desc.kinematic().calculate_end().pos());
+        }
+
+        /// Builds the frame out of the recorded points
+        pub fn finish(&self) -> Result<TaughtFrame, crate::Error> {
+            match (self.origin, self.x_point, self.xy_point) {
+                (Some(origin), Some(x_point), Some(xy_point)) => TaughtFrame::from_points(origin, x_point, xy_point),
+                _ => Err("All three points (origin, X-axis and XY-plane) must be taught to build a frame".into())
+            }
+        }
+
+        /// Builds the frame and inserts it into the `WorldObj` tree of the descriptor at the given path, returns the
+        /// frame to check its orthogonality error
+        pub fn finish_into<const C : usize, D : Descriptor<C>>(&self, desc : &mut D, path : &str) -> Result<TaughtFrame, crate::Error> {
+            let frame = self.finish()?;
+            frame.insert(desc.world_obj_mut(), path)?;
+            Ok(frame)
+        }
+    }
+//
@@ -0,0 +1,249 @@
+use glam::Vec3;
+use syact::SyncActuatorGroup;
+use syunit::*;
+
+use crate::{Descriptor, Robot};
+use crate::config::JsonConfig;
+use crate::desc::Kinematic;
+use crate::rcs::{LengthUnit, Point};
+use crate::tests::TestXYRobotComponents;
+
+const SYARM_CONF : &str = r#"{
+    "name": "SyArm_Mk1",
+    "conf_version": "0.0.1/2023/02/21",
+    "author": "Samuel Nösslböck",
+
+    "lk": { "u": 12, "s_f": 1.5 },
+
+    "anchor": [ 0.0, 0.0, 100.0 ],
+    "dims": [
+        [ 0.0, 0.0, 15.0 ],
+        [ 0.0, 285.0, 0.0 ],
+        [ 0.0, 285.0, 0.0 ],
+        [ 0.0, 45.0, 0.0 ]
+    ],
+    "axes": [
+        [ 0.0, 0.0, 1.0 ],
+        [ 1.0, 0.0, 0.0 ],
+        [ 1.0, 0.0, 0.0 ],
+        [ 1.0, 0.0, 0.0 ]
+    ],
+
+    "comps": [
+        {
+            "name": "Base",
+            "type_name": "syact::act::gear_bearing::GearJoint",
+            "obj": { "device": { "consts": "MOT_17HE15_1504S", "pin_dir": 17, "pin_step": 26 }, "ratio": 0.08333 },
+            "sim": { "mass": 0.2, "fric": 2.0 },
+            "meas": { "pin": 16, "set_val": 0.0, "dist": 0.0 },
+            "limit": { "vel": 5.0, "min": -3.14, "max": 3.14 }
+        },
+        {
+            "name": "Arm1",
+            "type_name": "syact::act::cylinder_triangle::CylinderTriangle",
+            "ang": { "offset": 1.5707964, "counter": false },
+            "limit": { "min": -1.5, "max": 1.5 }
+        },
+        {
+            "name": "Arm2",
+            "type_name": "syact::act::cylinder_triangle::CylinderTriangle",
+            "ang": { "offset": 0.0, "counter": true },
+            "limit": { "min": -2.5, "max": 2.5 }
+        },
+        {
+            "name": "Arm3",
+            "type_name": "syact::act::gear_bearing::GearJoint"
+        }
+    ]
+}"#;
+
+#[test]
+fn json_config_descriptor() {
+    let conf = JsonConfig::parse(SYARM_CONF).unwrap();
+    assert_eq!(conf.name, "SyArm_Mk1");
+    assert_eq!(conf.stepper_config().unwrap().voltage, 12.0);
+    assert!(conf.comps.iter().all(|comp| !comp.is_linear()));
+
+    let ang_confs = conf.ang_confs::<4>().unwrap();
+    assert!(ang_confs[2].counter);
+    assert!(conf.ang_confs::<3>().is_err());
+
+    let (min, max) = conf.gamma_limits::<4>().unwrap();
+    assert_eq!((min[2], max[2]), (Some(Gamma(-2.5)), Some(Gamma(2.5))));
+    assert_eq!(min[3], None);
+
+    // The kinematic tree
+    let mut desc = conf.descriptor::<4>().unwrap();
+    assert!(desc.world_obj().point("Base/Arm1/Arm2/Arm3/tcp").is_some());
+    assert!((desc.tcp().pos() - Vec3::new(0.0, 615.0, 115.0)).length() < 1e-3, "Bad TCP {:?}", desc.tcp());
+
+    // Numeric inverse kinematics
+    let phis = [ Phi(0.4), Phi(0.3), Phi(-0.6), Phi(-0.2) ];
+    desc.kinematic_mut().update(&phis).unwrap();
+    let target = desc.tcp().pose();
+    desc.kinematic_mut().update(&[ Phi::ZERO; 4 ]).unwrap();
+
+    let solved = desc.phis_for_pos(target.clone()).unwrap();
+    desc.kinematic_mut().update(&solved).unwrap();
+    assert!((desc.tcp().pos() - *target.pos()).length() < 0.01, "Bad solution {:?}", solved);
+
+    assert!(desc.phis_for_pos(crate::rcs::Position::new(0.0, 2000.0, 0.0)).is_err());
+}
+
+#[test]
+fn json_config_robot() {
+    let conf = JsonConfig::parse(r#"{
+        "name": "XY-Table",
+        "unit": "m",
+        "anchor": [ 0.0, 0.0, 0.0 ],
+        "dims": [ [ 0.0, 0.0, 0.0 ], [ 0.0, 0.0, -0.05 ] ],
+        "axes": [ [ 1.0, 0.0, 0.0 ], [ 0.0, 1.0, 0.0 ] ],
+        "comps": [
+            { "name": "x", "type_name": "syact::act::LinearAxis", "limit": { "vel": 0.2, "min": 0.0, "max": 0.4 },
+                "meas": { "set_val": 0.5, "dist": 2.0 } },
+            { "name": "y", "type_name": "syact::act::LinearAxis", "ang": { "offset": 0.01, "counter": false, "ratio": 20.0 },
+                "limit": { "vel": 0.1 } }
+        ]
+    }"#).unwrap();
+
+    assert_eq!(conf.unit, LengthUnit::Millimetre);
+    assert_eq!(conf.limits::<2>().unwrap().1[0], Some(Phi(400.0)));
+    assert_eq!(conf.comps[1].ang.offset, Delta(10.0));
+
+    let mut rob = conf.build_robot(|comps| {
+        assert_eq!(comps.len(), 2);
+        Ok(TestXYRobotComponents::new())
+    }, vec![]).unwrap();
+
+    assert_eq!(rob.ang_confs()[1].offset, Delta(10.0));
+    // Velocities are converted into `Gamma` velocities, 0.1 m/s with 20 per metre
+    assert_eq!(conf.velocities::<2>().unwrap()[1], Some(Velocity(100.0)));
+    assert!((rob.comps().velocity_max()[0].0 - 0.2).abs() < 1e-5, "Bad velocity {:?}", rob.comps().velocity_max());
+    assert!((rob.comps().velocity_max()[1].0 - 2.0).abs() < 1e-5, "Bad velocity {:?}", rob.comps().velocity_max());
+
+    // Measurement values are `Gamma` values and not converted
+    assert_eq!(conf.comps[0].meas.unwrap().set_val, 0.5);
+    assert_eq!(conf.comps[0].meas.unwrap().dist, 2.0);
+
+    let mut desc = conf.descriptor::<2>().unwrap();
+    let phis = desc.phis_for_pos(crate::rcs::Position::new(120.0, 80.0, -50.0)).unwrap();
+    assert!((phis[0].0 - 120.0).abs() < 0.01 && (phis[1].0 - 80.0).abs() < 0.01, "Bad phis {:?}", phis);
+    assert!(desc.phis_for_pos(crate::rcs::Position::new(500.0, 0.0, -50.0)).is_err());
+
+    desc.update(&mut rob, &phis).unwrap();
+
+    // Validation
+    assert!(JsonConfig::parse(r#"{ "name": "a", "anchor": [ 0, 0, 0 ], "dims": [ [ 0, 0, 0 ] ], "axes": [ ],
+        "comps": [ { "name": "x", "type_name": "a" } ] }"#).is_err());
+    assert!(JsonConfig::parse(r#"{ "name": "a", "anchor": [ 0, 0, 0 ], "dims": [ [ 0, 0, 0 ], [ 0, 0, 0 ] ], 
+        "axes": [ [ 1, 0, 0 ], [ 1, 0, 0 ] ], "comps": [ { "name": "x", "type_name": "a" }, { "name": "x", "type_name": "a" } ] }"#).is_err());
+    assert!(JsonConfig::parse(r#"{ "name": "a", "anchor": [ 0, 0, 0 ], "dims": [ [ 0, 0, 0 ] ], "axes": [ [ 1, 0, 0 ] ], 
+        "comps": [ { "name": "x", "type_name": "a", "limit": { "min": 1, "max": 0 } } ] }"#).is_err());
+
+    let err = JsonConfig::parse(r#"{ "name": "a", "anchor": [ 0, 0 ], "dims": [ ], "axes": [ ], "comps": [ ] }"#).unwrap_err();
+    assert!(err.to_string().contains("anchor"), "Bad error {}", err);
+}
+
+#[test]
+fn stepper_consts() {
+    use syact::StepperConst;
+    use crate::config::json::{stepper_const, STEPPER_CONST_NAMES};
+
+    for name in STEPPER_CONST_NAMES {
+        assert!(stepper_const(name).is_ok(), "Name {} not supported", name);
+    }
+
+    assert_eq!(stepper_const("MOT_17HE15_1504S").unwrap(), StepperConst::MOT_17HE15_1504S);
+
+    let err = stepper_const("NEMA_23").unwrap_err().to_string();
+    assert!(err.contains("NEMA_23") && err.contains("GEN, MOT_17HE15_1504S"), "Bad error {}", err);
+}
+
+#[test]
+fn angle_config_mappings() {
+    use core::f32::consts::PI;
+    use crate::config::{AngleConfig, LookupTable};
+    use crate::tests::TestXYRobot;
+
+    fn assert_round_trip(conf : &AngleConfig, phis : &[f32]) {
+        for phi in phis {
+            let gamma = conf.gamma_from_phi(Phi(*phi));
+            assert!((conf.phi_from_gamma(gamma).0 - phi).abs() < 1e-4, "{:?}: {} -> {:?}", conf, phi, gamma);
+
+            for dir in [ Direction::CW, Direction::CCW ] {
+                let gamma = conf.gamma_from_phi_dir(Phi(*phi), dir);
+                assert!((conf.phi_from_gamma_dir(gamma, dir).0 - phi).abs() < 1e-4);
+            }
+        }
+    }
+
+    let phis = [ -120.0, -1.0, 0.0, 0.5, 2.0, 45.0, 300.0 ];
+
+    // The defaults keep the plain offset/counter behaviour
+    let plain = AngleConfig { offset: Delta(1.0), counter: true, ..AngleConfig::EMPTY };
+    assert_eq!(plain.gamma_from_phi(Phi(3.0)), Gamma(-2.0));
+    assert_eq!(plain.phi_from_gamma(Gamma(-2.0)), Phi(3.0));
+    assert_round_trip(&plain, &phis);
+
+    // Gear ratio
+    let gear = AngleConfig::EMPTY.with_ratio(50.0);
+    assert!((gear.gamma_from_phi(Phi(PI / 2.0)).0 - 25.0 * PI).abs() < 1e-4);
+    assert_round_trip(&gear, &phis);
+
+    // Lead screw with 8 mm pitch, one revolution moves 8 mm
+    let mut screw = AngleConfig { offset: Delta(10.0), counter: true, ..AngleConfig::EMPTY.with_lead(8.0) };
+    assert!((screw.gamma_from_phi(Phi(18.0)).0 + 2.0 * PI).abs() < 1e-4);
+    assert_round_trip(&screw, &phis);
+
+    // Converting the axis into metres keeps the mapping
+    let gamma = screw.gamma_from_phi(Phi(18.0));
+    screw.scale_phi(0.001);
+    assert!((screw.phi_from_gamma(gamma).0 - 0.018).abs() < 1e-6);
+
+    // Crank-driven joint (decreasing), table values are extrapolated at both ends
+    let crank = AngleConfig::EMPTY.with_ratio(4.0).with_table(LookupTable::new(&[
+        [ -1.0, 1.2 ], [ 0.0, 0.0 ], [ 0.5, -0.4 ], [ 1.0, -0.6 ]
+    ]).unwrap());
+    crank.validate().unwrap();
+    assert!((crank.gamma_from_phi(Phi(-0.2)).0 - 1.0).abs() < 1e-4);
+    assert!((crank.phi_from_gamma(Gamma(8.0)).0 + 1.0).abs() < 1e-4);
+    assert_round_trip(&crank, &[ -1.0, -0.5, -0.4, 0.0, 0.6, 1.5 ]);
+    assert!((crank.gamma_velocity(Velocity(1.0)).0 - 4.0 / 1.2).abs() < 1e-4);
+
+    // Tables are stored inline, the configurations stay `Copy` and serialize as list of pairs
+    let copies = [ crank; 2 ];
+    assert_eq!(copies[1], crank);
+
+    let json = serde_json::to_string(&crank).unwrap();
+    assert!(json.contains(r#""table":[[-1.0,1.2],[0.0,0.0],[0.5,-0.4],[1.0,-0.6]]"#), "Bad json {}", json);
+    assert_eq!(serde_json::from_str::<AngleConfig>(&json).unwrap(), crank);
+    assert!(!serde_json::to_string(&AngleConfig::EMPTY).unwrap().contains("table"));
+
+    // Backlash
+    let play = AngleConfig::EMPTY.with_backlash(Delta(0.02));
+    assert_eq!(play.gamma_from_phi_dir(Phi(1.0), Direction::CW), Gamma(1.01));
+    assert_eq!(play.gamma_from_phi_dir(Phi(1.0), Direction::CCW), Gamma(0.99));
+    assert_round_trip(&play, &phis);
+
+    // Invalid configurations
+    assert!(AngleConfig::EMPTY.with_ratio(0.0).validate().is_err());
+    assert!(AngleConfig::EMPTY.with_backlash(Delta(-0.1)).validate().is_err());
+    assert!(LookupTable::new(&[ [ 0.0, 0.0 ] ]).is_err());
+    assert!(LookupTable::new(&[ [ 1.0, 0.0 ], [ 0.0, 1.0 ] ]).is_err());
+    assert!(LookupTable::new(&[ [ 0.0, 0.0 ], [ 1.0, 1.0 ], [ 2.0, 0.5 ] ]).is_err());
+
+    let long : Vec<[f32; 2]> = (0 ..= LookupTable::MAX_LEN).map(|i| [ i as f32, i as f32 ]).collect();
+    assert!(LookupTable::new(&long[.. LookupTable::MAX_LEN]).is_ok());
+    assert!(LookupTable::new(&long).is_err());
+    assert!(serde_json::from_str::<AngleConfig>(r#"{ "offset": 0, "counter": false, "table": [ [ 1, 0 ], [ 0, 1 ] ] }"#).is_err());
+
+    // Robots reject invalid configurations
+    let bad = [ AngleConfig::EMPTY.with_ratio(0.0), AngleConfig::EMPTY ];
+    let err = TestXYRobot::try_new(bad, TestXYRobotComponents::new(), vec![]).err().unwrap();
+    assert!(err.to_string().contains("component 0"), "Bad error {}", err);
+    assert!(std::panic::catch_unwind(|| TestXYRobot::new(bad, TestXYRobotComponents::new(), vec![])).is_err());
+
+    // Old configurations without the new fields still parse
+    let parsed : AngleConfig = serde_json::from_str(r#"{ "offset": 0.5, "counter": false }"#).unwrap();
+    assert_eq!(parsed, AngleConfig { offset: Delta(0.5), ..AngleConfig::EMPTY });
+}
@@ -0,0 +1,584 @@
+use core::f32::consts::PI;
+use std::sync::Arc;
+
+use glam::{Mat3, Vec3};
+use syunit::*;
+
+use crate::Descriptor;
+use crate::config::{AngleConfig, ArmConfig, AxisConfig, Elbow, Shoulder, Wrist};
+use crate::desc::{DhConvention, DhParam, DhTable, KinElement, Kinematic, Movement, SerialKinematic};
+use crate::desc::calib::{calibrate, CalibOptions, CalibReport, CalibSample};
+use crate::desc::reach::{ReachMap, ReachOptions};
+use crate::desc::urdf::UrdfChain;
+use crate::desc::common::{BeltXYZDescriptor, DeltaDescriptor, DeltaDims, LinearXYDescriptor, SixAxisDescriptor};
+use crate::rcs::{FramedPosition, LengthUnit, Point, PointRef, Position, WorldObj};
+use crate::tests::ARM_DIMS;
+
+const DELTA_DIMS : DeltaDims = DeltaDims {
+    base_radius: 100.0,
+    effector_radius: 30.0,
+    upper_arm: 120.0,
+    lower_arm: 300.0
+};
+
+#[test]
+fn delta_inverse_forward() {
+    let mut desc = DeltaDescriptor::new(DELTA_DIMS);
+
+    for pos in [ Vec3::new(0.0, 0.0, -250.0), Vec3::new(50.0, -30.0, -280.0), Vec3::new(-80.0, 60.0, -220.0) ] {
+        let phis = desc.phis_for_pos(Position::from(pos)).unwrap();
+        desc.kinematic_mut().update(&phis).unwrap();
+
+        let end = desc.kinematic().calculate_end();
+        assert!((*end.pos() - pos).length() < 1e-2, "Bad forward kinematics: {:?} -> {:?} -> {:?}", pos, phis, end);
+        assert!((desc.tcp().pos() - pos).length() < 1e-2);
+    }
+}
+
+#[test]
+fn delta_workspace() {
+    let desc = DeltaDescriptor::new(DELTA_DIMS)
+        .with_limits(Phi(-0.5), Phi(1.2));
+
+    assert!(desc.in_workspace(Vec3::new(0.0, 0.0, -250.0)));
+    assert!(!desc.in_workspace(Vec3::new(0.0, 0.0, 50.0)));
+    assert!(!desc.in_workspace(Vec3::new(0.0, 0.0, -1000.0)));
+    assert!(!desc.in_workspace(Vec3::new(500.0, 0.0, -250.0)));
+}
+
+#[test]
+fn delta_degenerated() {
+    // All joints of the lower arms in the center
+    let dims = DeltaDims { base_radius: 50.0, effector_radius: 50.0, upper_arm: 0.0, lower_arm: 100.0 };
+    assert!(dims.forward(&[ Phi::ZERO; 3 ]).is_err());
+
+    // Lower arms too short to reach the center, the state is reported instead of using a stale TCP
+    let desc = DeltaDescriptor::new(DeltaDims { lower_arm: 100.0, ..DELTA_DIMS });
+    assert!(desc.kinematic().try_calculate_end().is_err());
+}
+
+#[test]
+fn belt_inverse_forward() {
+    let mut desc = BeltXYZDescriptor::core_xy();
+
+    for pos in [ Vec3::new(100.0, 50.0, 10.0), Vec3::new(-20.0, 70.0, 0.0), Vec3::new(0.0, -35.0, 5.0) ] {
+        let phis = desc.phis_for_pos(Position::from(pos)).unwrap();
+        desc.kinematic_mut().update(&phis).unwrap();
+
+        assert!((desc.tcp().pos() - pos).length() < 1e-3, "Bad forward kinematics: {:?} -> {:?} -> {:?}", pos, phis, desc.tcp());
+    }
+
+    // Moving only one motor moves the head diagonally
+    desc.kinematic_mut().update(&[ Phi(10.0), Phi::ZERO, Phi::ZERO ]).unwrap();
+    assert!((desc.tcp().pos() - Vec3::new(5.0, 5.0, 0.0)).length() < 1e-3);
+}
+
+#[test]
+fn six_axis_solutions() {
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+
+    // The last position is too far away to be reached over the back of the arm
+    for (phis, count) in [
+        ([ Phi(0.3), Phi(0.4), Phi(0.2), Phi(0.5), Phi(0.6), Phi(-0.4) ], 8),
+        ([ Phi(-1.2), Phi(-0.3), Phi(0.9), Phi(-1.5), Phi(-0.8), Phi(2.0) ], 8),
+        ([ Phi(2.5), Phi(0.1), Phi(-0.6), Phi(0.2), Phi(1.3), Phi(0.0) ], 4)
+    ] {
+        desc.kinematic_mut().update(&phis).unwrap();
+        let target = desc.kinematic().calculate_end();
+
+        let solutions = desc.solutions(&target);
+        assert_eq!(solutions.len(), count);
+
+        // The original joint values must be one of the solutions
+        assert!(solutions.iter().any(|sol| 
+            sol.phis.iter().zip(phis).all(|(a, b)| (a.0 - b.0).abs() < 1e-3)
+        ), "Original phis {:?} missing in {:?}", phis, solutions);
+
+        for sol in solutions {
+            desc.kinematic_mut().update(&sol.phis).unwrap();
+            let end = desc.kinematic().calculate_end();
+
+            assert!((*end.pos() - *target.pos()).length() < 1e-2, "Bad position for {:?}", sol);
+            assert!(end.ori().abs_diff_eq(*target.ori(), 1e-4), "Bad orientation for {:?}", sol);
+        }
+    }
+}
+
+#[test]
+fn six_axis_config() {
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+    let target = Position::new_ori(Vec3::new(400.0, 100.0, 300.0), Mat3::from_rotation_y(0.5));
+
+    for config in [ 
+        ArmConfig::new(Shoulder::Front, Elbow::Up, Wrist::NoFlip),
+        ArmConfig::new(Shoulder::Back, Elbow::Down, Wrist::Flip)
+    ] {
+        *desc.axis_config_mut() = config.clone();
+        let phis = desc.phis_for_pos(target.clone()).unwrap();
+        
+        let sol = desc.solutions(&target).into_iter().find(|sol| sol.phis == phis).unwrap();
+        assert!(sol.matches(&config));
+    }
+
+    assert!(desc.phis_for_pos(Position::new(2000.0, 0.0, 0.0)).is_err());
+}
+
+#[test]
+fn dh_planar_arm() {
+    let standard = DhTable::new(DhConvention::Standard, vec![
+        DhParam::revolute(100.0, 0.0, 0.0, 0.0),
+        DhParam::revolute(50.0, 0.0, 0.0, 0.0)
+    ]);
+    let modified = DhTable::new(DhConvention::Modified, vec![
+        DhParam::revolute(0.0, 0.0, 0.0, 0.0),
+        DhParam::revolute(100.0, 0.0, 0.0, 0.0)
+    ]).with_tcp(Position::new(50.0, 0.0, 0.0));
+
+    for table in [ standard, modified ] {
+        let (mut kin, wobj) = SerialKinematic::<2>::from_dh(&table).unwrap();
+        assert!(wobj.point("joint_0/joint_1/tcp").is_some());
+
+        let (q1, q2) = (0.4, -1.1);
+        kin.update(&[ Phi(q1), Phi(q2) ]).unwrap();
+
+        let expected = Vec3::new(
+            100.0 * q1.cos() + 50.0 * (q1 + q2).cos(),
+            100.0 * q1.sin() + 50.0 * (q1 + q2).sin(),
+            0.0
+        );
+        assert!((kin.tcp().pos() - expected).length() < 1e-3, "Bad TCP {:?} for {:?}", kin.tcp(), table.convention);
+        assert!(kin.tcp().borrow().ori().abs_diff_eq(Mat3::from_rotation_z(q1 + q2), 1e-5));
+    }
+}
+
+#[test]
+fn dh_prismatic_serde() {
+    let table : DhTable = serde_json::from_str(r#"{
+        "convention": "Standard",
+        "joints": [
+            { "alpha": -1.5707964 },
+            { "joint": "Prismatic", "d": 10.0 }
+        ]
+    }"#).unwrap();
+
+    let (mut kin, _) = SerialKinematic::<2>::from_dh(&table).unwrap();
+    kin.update(&[ Phi::ZERO, Phi(25.0) ]).unwrap();
+
+    // The twist of the first joint tilts the Z-axis of the prismatic joint into the Y-direction
+    assert!((kin.tcp().pos() - Vec3::new(0.0, 35.0, 0.0)).length() < 1e-3, "Bad TCP {:?}", kin.tcp());
+
+    assert!(SerialKinematic::<3>::from_dh(&table).is_err());
+}
+
+const TEST_URDF : &str = r#"<?xml version="1.0"?>
+<robot name="test_arm">
+    <link name="world"/>
+    <link name="base"/>
+    <link name="upper"/>
+    <link name="lower"/>
+    <link name="slider"/>
+    <link name="tool"/>
+
+    <joint name="mount" type="fixed">
+        <parent link="world"/>
+        <child link="base"/>
+        <origin xyz="0 0 0.1" rpy="0 0 0"/>
+    </joint>
+    <joint name="turn" type="continuous">
+        <parent link="base"/>
+        <child link="upper"/>
+        <axis xyz="0 0 1"/>
+    </joint>
+    <joint name="tilt" type="revolute">
+        <parent link="upper"/>
+        <child link="lower"/>
+        <origin xyz="0.5 0 0" rpy="0 0 0"/>
+        <axis xyz="0 -1 0"/>
+        <limit lower="-1.0" upper="2.0" effort="1" velocity="1"/>
+    </joint>
+    <joint name="extend" type="prismatic">
+        <parent link="lower"/>
+        <child link="slider"/>
+        <origin xyz="0.2 0 0" rpy="0 0 0"/>
+        <axis xyz="1 0 0"/>
+        <limit lower="0.0" upper="0.3" effort="1" velocity="1"/>
+    </joint>
+    <joint name="flange" type="fixed">
+        <parent link="slider"/>
+        <child link="tool"/>
+        <origin xyz="0.05 0 0" rpy="0 0 0"/>
+    </joint>
+</robot>
+"#;
+
+#[test]
+fn urdf_chain() {
+    let mut chain = UrdfChain::<3>::parse(TEST_URDF, None).unwrap();
+    assert_eq!(chain.name, "test_arm");
+    assert!(chain.world_obj.point("turn/tilt/extend/tool").is_some());
+
+    let (lower, upper) = chain.limits();
+    assert_eq!(lower, [ None, Some(Phi(-1.0)), Some(Phi(0.0)) ]);
+    assert_eq!(upper, [ None, Some(Phi(2.0)), Some(Phi(0.3)) ]);
+
+    let (turn, tilt, extend) = (0.5f32, 0.3f32, 0.1f32);
+    chain.kinematic.update(&[ Phi(turn), Phi(tilt), Phi(extend) ]).unwrap();
+
+    // A rotation around the negative Y-axis lifts the arm
+    let reach = 0.2 + extend + 0.05;
+    let expected = Vec3::new(
+        (0.5 + reach * tilt.cos()) * turn.cos(),
+        (0.5 + reach * tilt.cos()) * turn.sin(),
+        0.1 + reach * tilt.sin()
+    );
+
+    assert!((chain.kinematic.tcp().pos() - expected).length() < 1e-5, "Bad TCP {:?}", chain.kinematic.tcp());
+
+    assert!(UrdfChain::<2>::parse(TEST_URDF, None).is_err());
+    assert!(UrdfChain::<1>::parse(TEST_URDF, Some("upper")).is_ok());
+
+    // URDF files are in metres and must be converted before being mixed with millimetre trees
+    assert_eq!(chain.unit(), LengthUnit::Metre);
+    assert!(WorldObj::zero().add_point("arm", PointRef::new(chain.world_obj.clone())).is_err());
+
+    chain.convert_unit(LengthUnit::Millimetre).unwrap();
+    assert_eq!(chain.limits().1, [ None, Some(Phi(2.0)), Some(Phi(300.0)) ]);
+    assert_eq!(chain.kinematic.phis()[2], Phi(100.0));
+    assert!((chain.kinematic.tcp().pos() - expected * 1000.0).length() < 1e-2, "Bad TCP {:?}", chain.kinematic.tcp());
+    assert!(WorldObj::zero().add_point("arm", PointRef::new(chain.world_obj.clone())).is_ok());
+}
+
+#[test]
+fn revolute_and_screw_elements() {
+    let wobj = WorldObj::zero()
+        .add_point_inline("tilt", PointRef::new(
+            WorldObj::new(10.0, 0.0, 0.0)
+                .add_point_inline("screw", PointRef::new(Position::new(0.0, 0.0, 5.0)))
+        ));
+
+    let axis = Vec3::new(1.0, 1.0, 0.0).normalize();
+    let mut kin = SerialKinematic::with_tcp([
+        KinElement::new(Movement::revolute(axis, Vec3::new(0.0, 0.0, 2.0)), wobj.point("tilt").unwrap()),
+        KinElement::new(Movement::screw(Vec3::Z, Vec3::new(1.0, 0.0, 0.0), 4.0), wobj.point("tilt/screw").unwrap())
+    ], Position::new(1.0, 0.0, 0.0));
+
+    // Half a turn around the tilted axis maps X to Y and Z to -Z (relative to the pivot)
+    kin.update(&[ Phi(PI), Phi::ZERO ]).unwrap();
+    assert!((kin.tcp().pos() - Vec3::new(10.0, 1.0, -1.0)).length() < 1e-4, "Bad TCP {:?}", kin.tcp());
+
+    // Half a turn of the screw moves by half of the lead and turns the TCP around the pivot
+    kin.update(&[ Phi::ZERO, Phi(PI) ]).unwrap();
+    assert!((kin.tcp().pos() - Vec3::new(11.0, 0.0, 7.0)).length() < 1e-4, "Bad TCP {:?}", kin.tcp());
+    assert!(kin.tcp().borrow().ori().abs_diff_eq(Mat3::from_rotation_z(PI), 1e-5));
+}
+
+#[test]
+fn six_axis_branch_selection() {
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+    let start = [ Phi(0.3), Phi(0.4), Phi(0.2), Phi(-2.5), Phi(-0.6), Phi(3.0) ];
+
+    desc.kinematic_mut().update(&start).unwrap();
+    let target = desc.kinematic().calculate_end();
+
+    // The nearest solution is the current position, even if the wrist is flipped
+    *desc.axis_config_mut() = ArmConfig::nearest();
+    let phis = desc.phis_for_pos(target.clone()).unwrap();
+    assert!(phis.iter().zip(start).all(|(a, b)| (a.0 - b.0).abs() < 1e-3), "Bad nearest solution {:?}", phis);
+
+    // Joints are unwrapped to the reference instead of jumping by a full turn
+    desc.axis_config_mut().configure(vec![ Phi(0.3), Phi(0.4), Phi(0.2), Phi(-2.5), Phi(-0.6), Phi(3.0 - 2.0 * PI) ]).unwrap();
+    let phis = desc.phis_for_pos(target.clone()).unwrap();
+    assert!((phis[5].0 - (3.0 - 2.0 * PI)).abs() < 1e-3, "Bad unwrapped solution {:?}", phis);
+
+    // Turns are added on top of the flagged solution
+    let flagged = ArmConfig::new(Shoulder::Front, Elbow::Up, Wrist::Flip);
+    *desc.axis_config_mut() = flagged.clone();
+    let base = desc.phis_for_pos(target.clone()).unwrap();
+
+    *desc.axis_config_mut() = flagged.with_turns(vec![ 0, 0, 0, 1, 0, -1 ]);
+    let turned = desc.phis_for_pos(target).unwrap();
+
+    assert!((turned[3].0 - base[3].0 - 2.0 * PI).abs() < 1e-4);
+    assert!((turned[5].0 - base[5].0 + 2.0 * PI).abs() < 1e-4);
+
+    // The reference joint values are runtime state and not saved with the configuration
+    let mut conf = ArmConfig::nearest();
+    conf.configure(start.to_vec()).unwrap();
+
+    let json = serde_json::to_string(&conf).unwrap();
+    assert!(!json.contains("phis"), "Reference values serialized: {}", json);
+
+    let loaded : ArmConfig = serde_json::from_str(&json).unwrap();
+    assert!(loaded.phis().is_empty());
+    assert_eq!(loaded.selection, conf.selection);
+}
+
+#[test]
+fn serial_tcp_shared_with_tree() {
+    let wobj = WorldObj::zero()
+        .add_point_inline("x", PointRef::new(
+            WorldObj::zero().add_point_inline("y", PointRef::new(
+                WorldObj::zero().add_point_inline("z", PointRef::new(Position::zero()))
+            ))
+        ));
+
+    let mut kin = SerialKinematic::new([
+        KinElement::new(Movement::Linear(Vec3::X), wobj.point("x").unwrap()),
+        KinElement::new(Movement::Linear(Vec3::Y), wobj.point("x/y").unwrap())
+    ]);
+
+    // The TCP is the point of the last segment, relative to its parent
+    assert!(!kin.is_tcp_detached());
+    assert!(Arc::ptr_eq(kin.tcp(), kin.segments()[1].point()));
+
+    kin.update(&[ Phi(5.0), Phi(3.0) ]).unwrap();
+    assert!((kin.tcp().pos() - Vec3::new(0.0, 3.0, 0.0)).length() < 1e-5, "Bad TCP {:?}", kin.tcp());
+    assert!((*kin.calculate_end().pos() - Vec3::new(5.0, 3.0, 0.0)).length() < 1e-5);
+
+    // Points attached to the last segment are carried by the TCP
+    let attached = kin.tcp().borrow().as_wo().and_then(|wo| wo.point("z")).unwrap();
+    assert!(Arc::ptr_eq(&attached, &wobj.point("x/y/z").unwrap()));
+}
+
+#[test]
+fn linear_tcp_with_tool() {
+    use crate::Robot;
+    use crate::tests::{TestTool, TestXYRobot};
+
+    let mut desc = LinearXYDescriptor::new();
+
+    // The TCP is the tool tip in base coordinates
+    assert!(desc.kinematic().is_tcp_detached());
+    desc.kinematic_mut().update(&[ Phi(5.0), Phi(3.0) ]).unwrap();
+    assert!((desc.tcp().pos() - Vec3::new(5.0, 3.0, 0.0)).length() < 1e-5, "Bad TCP {:?}", desc.tcp());
+
+    // Changing the tool moves the TCP right away
+    let mut rob = TestXYRobot::new([ AngleConfig::EMPTY; 2 ], crate::tests::TestXYRobotComponents::new(), 
+        vec![ Box::new(TestTool(Vec3::new(1.0, 2.0, -10.0))) ]);
+    rob.switch_tool(&mut desc, Some(0));
+    assert!((desc.tcp().pos() - Vec3::new(6.0, 5.0, -10.0)).length() < 1e-5, "Bad TCP {:?}", desc.tcp());
+
+    // Inverse kinematics and TCP agree
+    let target = Position::new(20.0, 10.0, -10.0);
+    let phis = desc.phis_for_pos(target.clone()).unwrap();
+    desc.kinematic_mut().update(&phis).unwrap();
+    assert!((desc.tcp().pos() - *target.pos()).length() < 1e-5, "Bad TCP {:?}", desc.tcp());
+}
+
+#[test]
+fn tool_extends_tcp() {
+    let tool = Position::new_ori(Vec3::new(50.0, 0.0, 20.0), Mat3::from_rotation_y(0.5));
+
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+    desc.kinematic_mut().set_tool(tool.clone());
+
+    let target = Position::new_ori(Vec3::new(350.0, -120.0, 400.0), Mat3::from_rotation_z(0.3) * Mat3::from_rotation_y(1.2));
+    let phis = desc.phis_for_pos(target.clone()).unwrap();
+    desc.kinematic_mut().update(&phis).unwrap();
+
+    assert!((desc.tcp().pos() - *target.pos()).length() < 1e-2, "Bad TCP {:?}", desc.tcp());
+    assert!(desc.tcp().borrow().ori().abs_diff_eq(*target.ori(), 1e-4));
+
+    let mut desc = BeltXYZDescriptor::h_bot();
+    desc.kinematic_mut().set_tool(tool);
+
+    let phis = desc.phis_for_pos(Position::new(100.0, 20.0, 30.0)).unwrap();
+    desc.kinematic_mut().update(&phis).unwrap();
+
+    assert!((desc.tcp().pos() - Vec3::new(100.0, 20.0, 30.0)).length() < 1e-3, "Bad TCP {:?}", desc.tcp());
+    assert!((desc.kinematic().segments()[2].pos() - Vec3::new(0.0, 0.0, 10.0)).length() < 1e-3);
+}
+
+#[test]
+fn reach_map() {
+    let table = DhTable::new(DhConvention::Standard, vec![
+        DhParam::revolute(100.0, 0.0, 0.0, 0.0),
+        DhParam::revolute(50.0, 0.0, 0.0, 0.0)
+    ]);
+    let (mut kin, _) = SerialKinematic::<2>::from_dh(&table).unwrap();
+    kin.update(&[ Phi(0.2), Phi(0.3) ]).unwrap();
+
+    let limits = [ (Phi(-PI), Phi(PI)), (Phi(-PI), Phi(PI)) ];
+    let map = ReachMap::sample(&mut kin, &limits, &ReachOptions::new(10.0, 73)).unwrap();
+
+    // The planar arm reaches a ring between 50 and 150
+    assert!(map.is_reachable(Vec3::new(149.0, 0.0, 0.0)));
+    assert!(map.is_reachable(Vec3::new(0.0, -100.0, 0.0)));
+    assert!(!map.is_reachable(Vec3::new(20.0, 0.0, 0.0)));
+    assert!(!map.is_reachable(Vec3::new(200.0, 0.0, 0.0)));
+    assert!(map.iter().all(|(pos, _)| pos.z == 5.0));
+
+    // Only the approach directions within the XY-plane can be reached
+    assert!(map.iter().all(|(_, voxel)| voxel.dexterity() <= 0.125));
+
+    // The kinematic is reset after sampling
+    assert_eq!(kin.phis(), [ Phi(0.2), Phi(0.3) ]);
+
+    let mut csv = Vec::new();
+    map.write_csv(&mut csv).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap().lines().count(), map.len() + 1);
+
+    let mut ply = Vec::new();
+    map.write_ply(&mut ply).unwrap();
+    assert!(String::from_utf8(ply).unwrap().contains(&format!("element vertex {}", map.len())));
+
+    // Errors of the final flush are reported
+    struct FullDisk;
+
+    impl std::io::Write for FullDisk {
+        fn write(&mut self, _ : &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("No space left"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    assert!(map.write_csv(std::io::BufWriter::new(FullDisk)).is_err());
+    assert!(map.write_ply(std::io::BufWriter::new(FullDisk)).is_err());
+
+    let mut map = ReachMap::new(1.0);
+    for dir in [ Vec3::X, Vec3::Y, Vec3::Z, -Vec3::Z, Vec3::X ] {
+        map.insert(Vec3::new(0.5, 0.5, 0.5), dir);
+    }
+
+    let voxel = map.voxel(Vec3::ZERO).unwrap();
+    assert_eq!(voxel.hits, 5);
+    assert_eq!(voxel.direction_count(), 4);
+}
+
+#[test]
+fn calibration() {
+    let link_errors = [ Vec3::new(0.5, -0.3, 1.0), Vec3::new(0.0, 0.8, -0.4), Vec3::new(-1.2, 0.0, 0.6), 
+        Vec3::ZERO, Vec3::new(0.3, 0.0, -0.2), Vec3::ZERO ];
+    let joint_errors = [ 0.01, -0.02, 0.015, 0.0, 0.01, 0.0 ];
+
+    // The "real" robot, deviating from the nominal model
+    let mut real = SixAxisDescriptor::new(ARM_DIMS);
+    for (seg, err) in real.kinematic().segments().iter().zip(link_errors) {
+        *seg.base().borrow_mut().pos_mut() += err;
+    }
+
+    let mut seed = 12345u32;
+    let mut rand = move || {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0
+    };
+
+    let samples : Vec<CalibSample<6>> = (0 .. 40).map(|_| {
+        let phis = [ Phi(rand() * 2.0), Phi(rand()), Phi(rand()), Phi(rand() * 2.0), Phi(rand()), Phi(rand() * 2.0) ];
+        let mut real_phis = phis;
+        for (phi, err) in real_phis.iter_mut().zip(joint_errors) {
+            *phi += Delta(err);
+        }
+
+        real.kinematic_mut().update(&real_phis).unwrap();
+        CalibSample::new(phis, *real.kinematic().calculate_end().pos())
+    }).collect();
+
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+    let report = calibrate(desc.kinematic_mut(), &samples, &CalibOptions::default()).unwrap();
+
+    assert!(report.rms_before() > 1.0, "Bad residuals before {}", report.rms_before());
+    assert!(report.rms_after() < 1e-2, "Bad residuals after {}", report.rms_after());
+    assert!(report.max_after() < 5e-2);
+
+    // The kinematic is left unchanged until the report is applied
+    assert_eq!(desc.kinematic().segments()[1].base().pos(), Vec3::new(ARM_DIMS.shoulder_offset, 0.0, ARM_DIMS.base_height));
+
+    let mut ang_confs = [ AngleConfig::EMPTY; 6 ];
+    report.apply(desc.kinematic_mut(), &mut ang_confs).unwrap();
+
+    let sample = &samples[7];
+    let mut phis = sample.phis;
+    for (phi, conf) in phis.iter_mut().zip(&ang_confs) {
+        *phi += conf.offset;
+    }
+    desc.kinematic_mut().update(&phis).unwrap();
+    assert!((*desc.kinematic().calculate_end().pos() - sample.measured).length() < 5e-2);
+
+    // The corrections are applied to the shared tree
+    let corrected = desc.world_obj().point("base/shoulder").unwrap().pos();
+    assert_eq!(corrected, Vec3::new(ARM_DIMS.shoulder_offset, 0.0, ARM_DIMS.base_height) + Vec3::from(report.link_offsets[1]));
+
+    let json = serde_json::to_string(&report).unwrap();
+    let parsed : CalibReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.joint_offsets, report.joint_offsets);
+}
+
+#[test]
+fn calibration_restores_on_error() {
+    /// A six axis kinematic rejecting `Phi` values outside of `-PI ..= PI`
+    #[derive(Debug)]
+    struct LimitedKinematic(SerialKinematic<6>);
+
+    impl Kinematic<6> for LimitedKinematic {
+        fn segments(&self) -> &[KinElement; 6] { self.0.segments() }
+        fn segments_mut(&mut self) -> &mut [KinElement; 6] { self.0.segments_mut() }
+        fn tcp(&self) -> &PointRef { self.0.tcp() }
+        fn tcp_mut(&mut self) -> &mut PointRef { self.0.tcp_mut() }
+        fn tool(&self) -> &Position { self.0.tool() }
+        fn set_tool(&mut self, tool : Position) { self.0.set_tool(tool) }
+        fn calculate_end(&self) -> Position { self.0.calculate_end() }
+
+        fn update(&mut self, phis : &[Phi; 6]) -> Result<(), crate::Error> {
+            if phis.iter().any(|phi| phi.0.abs() > PI) {
+                return Err("Phi out of range".into());
+            }
+
+            self.0.update(phis)
+        }
+    }
+
+    let desc = SixAxisDescriptor::new(ARM_DIMS);
+    let mut kin = LimitedKinematic(SerialKinematic::new(core::array::from_fn(|i| {
+        let seg = &desc.kinematic().segments()[i];
+        KinElement::new(Movement::revolute(Vec3::Z, Vec3::ZERO), seg.base().clone())
+    })));
+
+    let phis_0 = [ Phi(0.1), Phi(0.2), Phi(0.3), Phi(0.0), Phi(0.0), Phi(0.0) ];
+    kin.update(&phis_0).unwrap();
+    let bases : Vec<Vec3> = kin.segments().iter().map(|seg| seg.base().pos()).collect();
+
+    let samples = [
+        CalibSample::new([ Phi(0.5); 6 ], Vec3::new(100.0, 0.0, 300.0)),
+        CalibSample::new([ Phi(4.0); 6 ], Vec3::new(0.0, 100.0, 300.0))
+    ];
+
+    assert!(calibrate(&mut kin, &samples, &CalibOptions::default()).is_err());
+    assert_eq!(kin.segments().iter().map(|seg| seg.base().pos()).collect::<Vec<_>>(), bases);
+    assert_eq!(kin.phis(), phis_0);
+}
+
+#[test]
+fn framed_positions() {
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+    desc.world_obj_mut().add_point("fixtures", PointRef::new(
+        WorldObj::new(300.0, 0.0, 0.0)
+            .add_point_inline("pallet1", PointRef::new(
+                WorldObj::from_pos(Position::new_ori(Vec3::new(0.0, 100.0, 50.0), Mat3::from_rotation_z(PI / 2.0)))
+            ))
+    )).unwrap();
+
+    let target = FramedPosition::new("fixtures/pallet1", Position::new_ori(Vec3::new(20.0, 0.0, 100.0), Mat3::from_rotation_y(PI / 2.0)));
+    let resolved = target.resolve(desc.world_obj()).unwrap();
+    assert!((*resolved.pos() - Vec3::new(300.0, 120.0, 150.0)).length() < 1e-4, "Bad position {:?}", resolved);
+
+    let phis = desc.phis_for_framed(&target).unwrap();
+    assert_eq!(phis, desc.phis_for_pos(resolved).unwrap());
+
+    // Re-teaching the frame moves every position relative to it
+    *desc.world_obj().point("fixtures/pallet1").unwrap().borrow_mut().pos_mut() = Vec3::new(0.0, -100.0, 50.0);
+    let moved = target.resolve(desc.world_obj()).unwrap();
+    assert!((*moved.pos() - Vec3::new(300.0, -80.0, 150.0)).length() < 1e-4, "Bad position {:?}", moved);
+
+    assert!(FramedPosition::new("fixtures/pallet2", Position::zero()).resolve(desc.world_obj()).is_err());
+
+    let json = serde_json::to_string(&target).unwrap();
+    let parsed : FramedPosition = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.frame.as_deref(), Some("fixtures/pallet1"));
+
+    let base : FramedPosition = serde_json::from_str(r#"{ "pos": { "pos": [1, 2, 3] } }"#).unwrap();
+    assert!(base.frame.is_none());
+}
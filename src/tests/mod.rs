@@ -9,9 +9,27 @@ use syunit::*;
 use tokio::task::JoinSet;
 
 use crate::config::AngleConfig;
+use crate::desc::common::SixAxisDims;
 use crate::prelude::StepperRobot;
 use crate::Robot;
 
+// Submodules
+    mod config;
+    mod desc;
+    mod rcs;
+    mod teach;
+// 
+
+/// Dimensions of the six-axis arm shared by all tests
+pub const ARM_DIMS : SixAxisDims = SixAxisDims {
+    base_height: 200.0,
+    shoulder_offset: 50.0,
+    upper_arm: 300.0,
+    elbow_offset: 40.0,
+    forearm: 280.0,
+    wrist_length: 80.0
+};
+
 // SimPin
 
 // Helper structs
@@ -77,6 +95,31 @@ impl TestXYRobot {
     }
 }
 
+/// A tool without any functions, its tip is placed at the characteristic vector
+pub struct TestTool(pub glam::Vec3);
+
+impl syact::Setup for TestTool { }
+
+impl syact::Dismantle for TestTool { }
+
+impl crate::robs::Tool for TestTool {
+    fn get_json(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+
+    fn vec(&self) -> glam::Vec3 {
+        self.0
+    }
+
+    fn inertia(&self) -> Inertia {
+        Inertia::ZERO
+    }
+
+    fn mass(&self) -> f32 {
+        0.0
+    }
+}
+
 #[tokio::test]
 async fn move_j_test() {
     let mut rob = TestXYRobot::new_simple();
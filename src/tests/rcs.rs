@@ -0,0 +1,619 @@
+use core::f32::consts::PI;
+
+use glam::{Mat3, Quat, Vec3};
+
+use crate::rcs::{EulerOrder, Point, PointRef, Position, Transform, WorldObj};
+
+#[test]
+fn orientation_representations() {
+    let ori = Mat3::from_rotation_z(0.4) * Mat3::from_rotation_x(-1.1) * Mat3::from_rotation_y(0.7);
+    let pos = Position::new_ori(Vec3::new(1.0, 2.0, 3.0), ori);
+
+    for order in [ EulerOrder::XYZ, EulerOrder::XZY, EulerOrder::YXZ, EulerOrder::YZX, EulerOrder::ZXY, EulerOrder::ZYX ] {
+        let angles = pos.euler(order);
+        assert!(order.to_mat3(angles).abs_diff_eq(ori, 1e-5), "Bad decomposition for {:?}", order);
+        assert!(Position::new_euler(*pos.pos(), order, angles).ori().abs_diff_eq(ori, 1e-5));
+    }
+
+    // Singularity
+    let locked = EulerOrder::XYZ.to_mat3([ 0.3, PI / 2.0, 0.2 ]);
+    assert!(EulerOrder::XYZ.to_mat3(EulerOrder::XYZ.angles(&locked)).abs_diff_eq(locked, 1e-5));
+
+    let [ roll, pitch, yaw ] = pos.rpy();
+    assert!(Position::new_rpy(Vec3::ZERO, roll, pitch, yaw).ori().abs_diff_eq(ori, 1e-5));
+
+    let rpy = Position::new_rpy(Vec3::ZERO, 0.1, 0.2, 0.3);
+    assert!(rpy.ori().abs_diff_eq(Mat3::from_rotation_z(0.3) * Mat3::from_rotation_y(0.2) * Mat3::from_rotation_x(0.1), 1e-6));
+
+    assert!(Position::new_quat(Vec3::ZERO, pos.quat()).ori().abs_diff_eq(ori, 1e-5));
+
+    let (axis, angle) = pos.axis_angle();
+    assert!((0.0 ..= PI).contains(&angle));
+    assert!(Position::new_axis_angle(Vec3::ZERO, axis, angle).ori().abs_diff_eq(ori, 1e-5));
+}
+
+#[test]
+fn orientation_serde() {
+    let expected = Mat3::from_rotation_z(PI / 2.0);
+
+    for ori in [
+        r#"[[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]"#,
+        r#"{ "rpy": [0.0, 0.0, 90.0], "degrees": true }"#,
+        r#"{ "euler": [1.5707964, 0.0, 0.0], "order": "ZYX" }"#,
+        r#"{ "quat": [0.0, 0.0, 0.70710677, 0.70710677] }"#,
+        r#"{ "axis": [0.0, 0.0, 2.0], "angle": 90, "degrees": true }"#
+    ] {
+        let pos : Position = serde_json::from_str(&format!(r#"{{ "pos": [1, 2, 3], "ori": {} }}"#, ori)).unwrap();
+        assert!(pos.ori().abs_diff_eq(expected, 1e-6), "Bad orientation for {}", ori);
+
+        let point : PointRef = serde_json::from_str(&format!(r#"{{ "pos": [1, 2, 3], "ori": {} }}"#, ori)).unwrap();
+        assert!(point.borrow().ori().abs_diff_eq(expected, 1e-6), "Bad orientation for {}", ori);
+    }
+
+    let wobj : WorldObj = serde_json::from_str(
+        r#"{ "pos": { "pos": [0, 0, 1], "ori": { "rpy": [0, 0, 90], "degrees": true } }, "sub": {} }"#
+    ).unwrap();
+    assert!(wobj.ori().abs_diff_eq(expected, 1e-6));
+
+    let rot = Position::new_quat(Vec3::X, Quat::from_rotation_y(0.5));
+    let json = serde_json::to_string(&rot).unwrap();
+    let parsed : Position = serde_json::from_str(&json).unwrap();
+    assert!(parsed.ori().abs_diff_eq(*rot.ori(), 1e-6));
+}
+
+#[test]
+fn world_pose() {
+    let wobj = WorldObj::from_pos(Position::new_ori(Vec3::new(0.0, 0.0, 10.0), Mat3::from_rotation_z(PI / 2.0)))
+        .add_point_inline("a", PointRef::new(
+            WorldObj::from_pos(Position::new_ori(Vec3::new(5.0, 0.0, 0.0), Mat3::from_rotation_x(PI / 2.0)))
+                .add_point_inline("b", PointRef::new(Position::new(0.0, 2.0, 0.0)))
+        ))
+        .add_point_inline("c", PointRef::new(Position::new(1.0, 0.0, 0.0)));
+
+    let root = wobj.world_pose("").unwrap();
+    assert_eq!(*root.pos(), Vec3::new(0.0, 0.0, 10.0));
+
+    let a = wobj.world_pose("a").unwrap();
+    assert!((*a.pos() - Vec3::new(0.0, 5.0, 10.0)).length() < 1e-5);
+
+    let b = wobj.world_pose("a/b").unwrap();
+    assert!((*b.pos() - Vec3::new(0.0, 5.0, 12.0)).length() < 1e-5, "Bad pose {:?}", b);
+    assert!(b.ori().abs_diff_eq(Mat3::from_rotation_z(PI / 2.0) * Mat3::from_rotation_x(PI / 2.0), 1e-6));
+
+    // Pose of "a/b" seen from "c"
+    let rel = wobj.relative_pose("c", "a/b").unwrap();
+    assert!((*rel.pos() - Vec3::new(4.0, 0.0, 2.0)).length() < 1e-5, "Bad relative pose {:?}", rel);
+    assert!(rel.ori().abs_diff_eq(Mat3::from_rotation_x(PI / 2.0), 1e-6));
+
+    assert!(wobj.world_pose("a/x").is_none());
+    assert!(wobj.world_pose("c/b").is_none());
+}
+
+#[test]
+fn rigid_transform() {
+    let tf = Transform::new(Mat3::from_rotation_z(PI / 2.0), Vec3::new(1.0, 0.0, 0.0));
+    let other = Transform::new(Mat3::from_rotation_x(0.3), Vec3::new(0.0, 2.0, 0.0));
+
+    let v = Vec3::new(1.0, 2.0, 3.0);
+    assert!(((tf * other) * v - tf * (other * v)).length() < 1e-5);
+    assert!(((tf.inverse() * tf) * v - v).length() < 1e-5);
+    assert!((tf * v - Vec3::new(-1.0, 1.0, 3.0)).length() < 1e-5);
+
+    // Transforming a pose rotates its orientation as well
+    let mut pos = Position::new_ori(Vec3::new(1.0, 0.0, 0.0), Mat3::from_rotation_y(0.5));
+    pos.apply(&tf);
+    assert!((*pos.pos() - Vec3::new(1.0, 1.0, 0.0)).length() < 1e-5);
+    assert!(pos.ori().abs_diff_eq(Mat3::from_rotation_z(PI / 2.0) * Mat3::from_rotation_y(0.5), 1e-6));
+
+    let mut rotated = Position::new(1.0, 0.0, 0.0);
+    rotated.transform(Mat3::from_rotation_z(PI / 2.0));
+    assert!(rotated.ori().abs_diff_eq(Mat3::from_rotation_z(PI / 2.0), 1e-6));
+
+    // Moving a sub-tree keeps the relative poses of its children
+    let wobj = WorldObj::zero()
+        .add_point_inline("fixture", PointRef::new(
+            WorldObj::new(10.0, 0.0, 0.0)
+                .add_point_inline("part", PointRef::new(Position::new(0.0, 5.0, 0.0)))
+        ));
+
+    let before = wobj.relative_pose("fixture", "fixture/part").unwrap();
+    wobj.point("fixture").unwrap().apply(&Transform::from_rotation_about(Mat3::from_rotation_z(PI / 2.0), Vec3::ZERO));
+
+    let part = wobj.world_pose("fixture/part").unwrap();
+    assert!((*part.pos() - Vec3::new(-5.0, 10.0, 0.0)).length() < 1e-5, "Bad pose {:?}", part);
+    assert!(wobj.relative_pose("fixture", "fixture/part").unwrap().pos().abs_diff_eq(*before.pos(), 1e-5));
+    assert_eq!(Transform::from(&part).to_pos().pos(), part.pos());
+}
+
+#[tokio::test]
+async fn shared_between_tasks() {
+    fn assert_send_sync<T : Send + Sync>() { }
+    assert_send_sync::<PointRef>();
+    assert_send_sync::<WorldObj>();
+    assert_send_sync::<crate::desc::common::SixAxisDescriptor>();
+
+    let wobj = WorldObj::zero()
+        .add_point_inline("counter", PointRef::new(Position::zero()));
+
+    let mut set = tokio::task::JoinSet::new();
+
+    for _ in 0 .. 8 {
+        let point = wobj.point("counter").unwrap();
+        set.spawn(async move {
+            for _ in 0 .. 100 {
+                point.borrow_mut().shift(Vec3::X);
+            }
+        });
+    }
+
+    while let Some(res) = set.join_next().await {
+        res.unwrap();
+    }
+
+    assert_eq!(wobj.world_pose("counter").unwrap().pos().x, 800.0);
+}
+
+#[test]
+fn tree_editing() {
+    let mut wobj = WorldObj::zero()
+        .add_point_inline("fixtures", PointRef::new(
+            WorldObj::new(100.0, 0.0, 0.0)
+                .add_point_inline("pallet1", PointRef::new(
+                    WorldObj::from_pos(Position::new_ori(Vec3::new(0.0, 50.0, 0.0), Mat3::from_rotation_z(PI / 2.0)))
+                        .add_point_inline("origin", PointRef::new(Position::new(1.0, 0.0, 0.0)))
+                ))
+                .add_point_inline("pallet2", PointRef::new(
+                    WorldObj::new(0.0, -50.0, 0.0)
+                        .add_point_inline("origin", PointRef::new(Position::new(2.0, 0.0, 0.0)))
+                        .add_point_inline("corner", PointRef::new(Position::new(3.0, 0.0, 0.0)))
+                ))
+        ))
+        .add_point_inline("table", PointRef::new(WorldObj::new(0.0, 0.0, 20.0)));
+
+    assert!(wobj.add_point("a/b", PointRef::new(Position::zero())).is_err());
+    assert!(wobj.add_point("", PointRef::new(Position::zero())).is_err());
+    assert!(wobj.point("fixtures/pallet1/origin/x").is_none());
+
+    // Walk
+    let paths : Vec<String> = wobj.walk().into_iter().map(|(path, _)| path).collect();
+    assert_eq!(paths, [ 
+        "fixtures", "fixtures/pallet1", "fixtures/pallet1/origin", "fixtures/pallet2", "fixtures/pallet2/corner", 
+        "fixtures/pallet2/origin", "table" 
+    ]);
+
+    // Queries
+    let origins : Vec<String> = wobj.query("fixtures/*/origin").unwrap().into_iter().map(|(path, _)| path).collect();
+    assert_eq!(origins, [ "fixtures/pallet1/origin", "fixtures/pallet2/origin" ]);
+    assert_eq!(wobj.query("**/c?rner").unwrap().len(), 1);
+    assert_eq!(wobj.query("fixtures/pallet*").unwrap().len(), 2);
+    assert!(wobj.query("fixtures//origin").is_err());
+
+    // Rename
+    wobj.rename_point("fixtures/pallet2/corner", "edge").unwrap();
+    assert!(wobj.point("fixtures/pallet2/edge").is_some());
+    assert!(wobj.rename_point("fixtures/pallet2/edge", "origin").is_err());
+    assert!(wobj.rename_point("fixtures/pallet3", "pallet4").is_err());
+
+    // Reparent, keeping the world pose
+    let before = wobj.world_pose("fixtures/pallet1").unwrap();
+    let new_path = wobj.reparent("fixtures/pallet1", "table").unwrap();
+    assert_eq!(new_path, "table/pallet1");
+    assert!(wobj.point("fixtures/pallet1").is_none());
+
+    let after = wobj.world_pose("table/pallet1").unwrap();
+    assert!(after.pos().abs_diff_eq(*before.pos(), 1e-5) && after.ori().abs_diff_eq(*before.ori(), 1e-6));
+    assert!((*wobj.world_pose("table/pallet1/origin").unwrap().pos() - Vec3::new(100.0, 51.0, 0.0)).length() < 1e-4);
+
+    assert!(wobj.reparent("table", "table/pallet1").is_err());
+    assert!(wobj.reparent("fixtures/pallet2", "fixtures/pallet2/origin").is_err());
+    assert!(wobj.point("fixtures/pallet2").is_some());
+
+    // Remove
+    wobj.remove_point("fixtures/pallet2/edge").unwrap();
+    assert!(wobj.remove_point("fixtures/pallet2/edge").is_err());
+    assert_eq!(wobj.walk().len(), 6);
+}
+
+#[test]
+fn change_notifications() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::Descriptor;
+    use crate::desc::Kinematic;
+    use crate::desc::common::SixAxisDescriptor;
+    use crate::tests::ARM_DIMS;
+
+    let point = PointRef::new(Position::zero());
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let count_c = count.clone();
+    let id = point.subscribe(move |pose| {
+        assert_eq!(pose.pos().x, 1.0);
+        count_c.fetch_add(1, Ordering::Relaxed);
+    });
+
+    point.borrow_mut().shift(Vec3::X);
+    // Borrows without changes do not notify
+    point.borrow_mut().shift(Vec3::ZERO);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+
+    assert!(point.unsubscribe(id));
+    point.borrow_mut().shift(Vec3::X);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+
+    // Kinematic updates notify the observers of the moving frames
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+
+    let mut elbow = desc.kinematic().segments()[1].point().subscribe_channel();
+    let mut tcp = desc.tcp().subscribe_channel();
+
+    desc.kinematic_mut().update(&[ syunit::Phi(0.2); 6 ]).unwrap();
+    assert!(elbow.try_recv().unwrap().ori().abs_diff_eq(Mat3::from_rotation_y(0.2), 1e-6));
+    assert!(tcp.try_recv().is_ok());
+    assert!(elbow.try_recv().is_err());
+
+    // Dropped receivers end the subscription
+    drop(tcp);
+    desc.kinematic_mut().update(&[ syunit::Phi(0.3); 6 ]).unwrap();
+    assert!(!desc.tcp().has_observers());
+
+    // Observing a whole tree
+    let wobj = WorldObj::zero()
+        .add_point_inline("fixture", PointRef::new(
+            WorldObj::new(1.0, 0.0, 0.0)
+                .add_point_inline("part", PointRef::new(Position::zero()))
+        ));
+    let mut changes = wobj.subscribe_all();
+
+    wobj.point("fixture/part").unwrap().borrow_mut().shift(Vec3::Z);
+    let change = changes.try_recv().unwrap();
+    assert_eq!(change.path, "fixture/part");
+    assert_eq!(*change.pose.pos(), Vec3::Z);
+
+    // Kinematic motion is reported to the observers of the tree
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+    let base = desc.world_obj().point("base").unwrap().pose();
+    let mut changes = desc.world_obj().subscribe_all();
+
+    let mut phis = [ syunit::Phi::ZERO; 6 ];
+    phis[0] = syunit::Phi(0.2);
+    desc.kinematic_mut().update(&phis).unwrap();
+    let change = changes.try_recv().unwrap();
+    assert_eq!(change.path, "base");
+    assert!(change.pose.ori().abs_diff_eq(*base.ori() * Mat3::from_rotation_z(0.2), 1e-6), "Bad change {:?}", change);
+    assert!(changes.try_recv().is_err());
+
+    // The tree keeps the base pose
+    assert_eq!(desc.world_obj().point("base").unwrap().pose(), base);
+}
+
+#[test]
+fn point_reentry() {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    let point = PointRef::new(Position::zero());
+
+    // Nested borrows on the same thread are reported instead of deadlocking
+    {
+        let _guard = point.borrow_mut();
+        assert!(point.try_borrow().is_err());
+        assert!(point.try_borrow_mut().is_err());
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| { point.borrow(); })).is_err());
+    }
+
+    {
+        let _guard = point.borrow();
+        assert!(point.try_borrow().is_ok());
+        assert!(point.try_borrow_mut().is_err());
+    }
+
+    assert!(point.try_borrow_mut().is_ok());
+
+    // Observers may access the point they observe
+    let observed = Arc::new(AtomicBool::new(false));
+    let (point_c, observed_c) = (point.clone(), observed.clone());
+
+    point.subscribe(move |pose| {
+        assert_eq!(point_c.pos(), *pose.pos());
+        observed_c.store(true, Ordering::Relaxed);
+    });
+
+    point.borrow_mut().shift(Vec3::X);
+    assert!(observed.load(Ordering::Relaxed));
+
+    // Locks held by other threads are waited for
+    let point_c = point.clone();
+    let handle = thread::spawn(move || {
+        let mut guard = point_c.borrow_mut();
+        thread::sleep(Duration::from_millis(50));
+        guard.shift(Vec3::Y);
+    });
+
+    thread::sleep(Duration::from_millis(10));
+    let _ = point.try_borrow().unwrap();
+    handle.join().unwrap();
+
+    assert_eq!(point.pos(), Vec3::new(1.0, 1.0, 0.0));
+
+    // Nested reads succeed even if a writer of another thread is waiting
+    let outer = point.borrow();
+    let point_c = point.clone();
+    let handle = thread::spawn(move || point_c.borrow_mut().shift(Vec3::Z));
+
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(*point.try_borrow().unwrap().pos(), Vec3::new(1.0, 1.0, 0.0));
+    assert!(point.try_borrow_mut().is_err());
+    drop(outer);
+
+    handle.join().unwrap();
+    assert_eq!(point.pos(), Vec3::new(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn point_deserialization() {
+    // A world object with a plain position array, previously parsed as `Position` (the `Pos` variant was tried first
+    // and ignored the `sub` field)
+    let point : PointRef = serde_json::from_str(r#"{ "pos": [ 1, 2, 3 ], "sub": { "a": { "pos": [ 0, 0, 1 ] } } }"#).unwrap();
+    let wo = point.borrow().as_wo().cloned().expect("World object parsed as position");
+    assert_eq!(*wo.pos(), Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(wo.point("a").unwrap().pos(), Vec3::Z);
+
+    // Positions stay positions
+    let point : PointRef = serde_json::from_str(r#"{ "pos": [ 1, 2, 3 ] }"#).unwrap();
+    assert!(point.borrow().as_wo().is_none());
+    assert_eq!(point.pos(), Vec3::new(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn scene_files() {
+    let dir = std::env::temp_dir().join(format!("sybot_scene_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("fixtures")).unwrap();
+
+    std::fs::write(dir.join("fixtures/pallet.json"), r#"{
+        "version": 1,
+        "root": { "pos": { "pos": [ 0, 0, 0 ] }, "sub": { "origin": { "pos": [ 10, 0, 0 ] } } }
+    }"#).unwrap();
+
+    std::fs::write(dir.join("cell.json"), r#"{
+        "version": 1,
+        "root": {
+            "pos": { "pos": [ 0, 0, 0 ] },
+            "sub": {
+                "pallet1": { "include": "fixtures/pallet.json", "pos": { "pos": [ 500, 0, 0 ] } },
+                "pallet2": { "include": "fixtures/pallet.json", "pos": { "pos": [ 0, 500, 0 ], "ori": { "rpy": [ 0, 0, 90 ], "degrees": true } } }
+            }
+        }
+    }"#).unwrap();
+
+    let wobj = WorldObj::load(dir.join("cell.json")).unwrap();
+    assert!((*wobj.world_pose("pallet1/origin").unwrap().pos() - Vec3::new(510.0, 0.0, 0.0)).length() < 1e-4);
+    assert!((*wobj.world_pose("pallet2/origin").unwrap().pos() - Vec3::new(0.0, 510.0, 0.0)).length() < 1e-4);
+
+    // Roundtrip, the includes are written inline
+    wobj.save(dir.join("saved.json")).unwrap();
+    let loaded = WorldObj::load(dir.join("saved.json")).unwrap();
+    assert_eq!(loaded.walk().len(), wobj.walk().len());
+
+    let saved = std::fs::read_to_string(dir.join("saved.json")).unwrap();
+    assert!(!saved.contains("include"), "Includes not flattened: {}", saved);
+    assert!((*loaded.world_pose("pallet2/origin").unwrap().pos() - Vec3::new(0.0, 510.0, 0.0)).length() < 1e-4);
+
+    // Errors
+    std::fs::write(dir.join("bad.json"), r#"{ "version": 1, "root": { "pos": { "pos": [ 0, 0, 0 ] }, "sub": { "a": { "pos": [ 0, 0 ] } } } }"#).unwrap();
+    let err = WorldObj::load(dir.join("bad.json")).unwrap_err().to_string();
+    assert!(err.contains("root.sub.a"), "Bad error message: {}", err);
+
+    std::fs::write(dir.join("future.json"), r#"{ "version": 99, "root": { "pos": { "pos": [ 0, 0, 0 ] }, "sub": { } } }"#).unwrap();
+    assert!(WorldObj::load(dir.join("future.json")).unwrap_err().to_string().contains("version"));
+
+    std::fs::write(dir.join("zero.json"), r#"{ "version": 0, "root": { "pos": { "pos": [ 0, 0, 0 ] }, "sub": { } } }"#).unwrap();
+    assert!(WorldObj::load(dir.join("zero.json")).unwrap_err().to_string().contains("version"));
+
+    std::fs::write(dir.join("loop.json"), r#"{ "version": 1, "root": { "pos": { "pos": [ 0, 0, 0 ] }, "sub": { "a": { "include": "loop.json" } } } }"#).unwrap();
+    assert!(WorldObj::load(dir.join("loop.json")).unwrap_err().to_string().contains("includes itself"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn length_units() {
+    use crate::rcs::{Geometry, LengthUnit, Shape};
+
+    assert_eq!(LengthUnit::Inch.convert(2.0, LengthUnit::Millimetre), 50.8);
+    assert_eq!(LengthUnit::Millimetre.convert(250.0, LengthUnit::Metre), 0.25);
+
+    let dir = std::env::temp_dir().join(format!("sybot_units_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    std::fs::write(dir.join("jig.json"), r#"{
+        "version": 2,
+        "unit": "m",
+        "root": {
+            "pos": { "pos": [ 0, 0, 0 ] },
+            "sub": { "pin": { "pos": [ 0.1, 0, 0.02 ] } },
+            "geometry": [ { "shape": { "type": "box", "size": [ 0.2, 0.1, 0.02 ] } } ]
+        }
+    }"#).unwrap();
+
+    std::fs::write(dir.join("cell.json"), r#"{
+        "version": 2,
+        "unit": "in",
+        "root": {
+            "pos": { "pos": [ 0, 0, 0 ] },
+            "sub": { "jig": { "include": "jig.json", "pos": { "pos": [ 10, 0, 0 ] } } }
+        }
+    }"#).unwrap();
+
+    let wobj = WorldObj::load(dir.join("cell.json")).unwrap();
+    assert_eq!(wobj.unit(), LengthUnit::Millimetre);
+    assert!((*wobj.world_pose("jig/pin").unwrap().pos() - Vec3::new(354.0, 0.0, 20.0)).length() < 1e-3);
+
+    let jig = wobj.point("jig").unwrap();
+    assert_eq!(jig.borrow().as_wo().unwrap().geometry[0].shape.aabb().1, Vec3::new(100.0, 50.0, 10.0));
+
+    // Saving keeps the unit of the tree
+    let mut metres = wobj.clone();
+    metres.convert_unit(LengthUnit::Metre);
+    metres.save(dir.join("saved.json")).unwrap();
+    assert!(std::fs::read_to_string(dir.join("saved.json")).unwrap().contains(r#""unit": "m""#));
+
+    let loaded = WorldObj::load(dir.join("saved.json")).unwrap();
+    assert!((*loaded.world_pose("jig/pin").unwrap().pos() - Vec3::new(354.0, 0.0, 20.0)).length() < 1e-3);
+
+    // Trees with different units cannot be mixed
+    let mut cell = WorldObj::zero();
+    let metres = PointRef::new(metres);
+    assert!(cell.add_point("jig", metres.clone()).is_err());
+    assert!(cell.add_point("pin", PointRef::new(Position::new(1.0, 0.0, 0.0))).is_ok());
+
+    let sub = std::collections::HashMap::from([ ("jig".to_owned(), metres) ]);
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| WorldObj::from_pos_sub(Position::zero(), sub))).is_err());
+
+    let mut sphere = Geometry::new(Shape::Sphere { radius: 0.5 });
+    sphere.scale(LengthUnit::Inch.factor_to(LengthUnit::Millimetre));
+    assert_eq!(sphere.shape, Shape::Sphere { radius: 12.7 });
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn geometry() {
+    use crate::rcs::{Geometry, GeometryUsage, Mesh, Shape};
+
+    let obj = Mesh::parse_obj("# cube face\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1/1 2/2 3/3 4/4\nf -4 -3 -2\n").unwrap();
+    assert_eq!(obj.vertices.len(), 4);
+    assert_eq!(obj.triangles, [ [0, 1, 2], [0, 2, 3], [0, 1, 2] ]);
+    assert!(Mesh::parse_obj("v 0 0 0\nf 1 2 3\n").is_err());
+
+    let ascii = Mesh::parse_stl(b"solid test\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 2 0 0\nvertex 0 3 0\nendloop\nendfacet\nendsolid test\n").unwrap();
+    assert_eq!(ascii.triangles.len(), 1);
+    assert_eq!(ascii.aabb(), (Vec3::ZERO, Vec3::new(2.0, 3.0, 0.0)));
+
+    let mut binary = vec![ 0u8; 80 ];
+    binary.extend(1u32.to_le_bytes());
+    for value in [ 0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 4.0 ] {
+        binary.extend(value.to_le_bytes());
+    }
+    binary.extend([ 0u8; 2 ]);
+    let stl = Mesh::parse_stl(&binary).unwrap();
+    assert_eq!(stl.vertices, [ [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 4.0] ]);
+
+    assert_eq!(Shape::Capsule { radius: 1.0, height: 4.0 }.aabb().1, Vec3::new(1.0, 1.0, 3.0));
+
+    // Serialization with the scene
+    let dir = std::env::temp_dir().join(format!("sybot_geometry_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("part.obj"), "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+    std::fs::write(dir.join("scene.json"), r#"{
+        "version": 1,
+        "root": {
+            "pos": { "pos": [ 0, 0, 0 ] },
+            "sub": {
+                "table": { 
+                    "pos": [ 0, 0, 500 ], 
+                    "sub": { },
+                    "geometry": [
+                        { "shape": { "type": "box", "size": [ 1000, 800, 40 ] }, "usage": "collision" },
+                        { "shape": { "type": "mesh", "file": "part.obj" }, "pose": { "pos": [ 0, 0, 20 ] } }
+                    ]
+                }
+            }
+        }
+    }"#).unwrap();
+
+    let wobj = WorldObj::load(dir.join("scene.json")).unwrap();
+    let table = wobj.point("table").unwrap();
+    {
+        let table = table.borrow();
+        let geometry = &table.as_wo().unwrap().geometry;
+
+        assert_eq!(geometry[0].usage, GeometryUsage::Collision);
+        assert_eq!(geometry[0].shape, Shape::Box { size: [ 1000.0, 800.0, 40.0 ] });
+
+        let Shape::Mesh(mesh) = &geometry[1].shape else { panic!("Bad shape") };
+        assert_eq!(mesh.triangles.len(), 1);
+        assert_eq!(*geometry[1].pose.pos(), Vec3::new(0.0, 0.0, 20.0));
+    }
+
+    wobj.save(dir.join("saved.json")).unwrap();
+    let saved = WorldObj::load(dir.join("saved.json")).unwrap();
+    assert_eq!(saved.point("table").unwrap().borrow().as_wo().unwrap().geometry, table.borrow().as_wo().unwrap().geometry);
+
+    // Mesh paths are saved as written in the scene, paths of included scenes relative to the including scene
+    assert!(std::fs::read_to_string(dir.join("saved.json")).unwrap().contains(r#""file": "part.obj""#));
+
+    std::fs::create_dir_all(dir.join("parts")).unwrap();
+    std::fs::write(dir.join("parts/part.obj"), "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+    std::fs::write(dir.join("parts/part.json"), r#"{
+        "version": 2,
+        "root": { "pos": { "pos": [ 0, 0, 0 ] }, "sub": { }, "geometry": [ { "shape": { "type": "mesh", "file": "part.obj" } } ] }
+    }"#).unwrap();
+    std::fs::write(dir.join("cell.json"), r#"{
+        "version": 2,
+        "root": { "pos": { "pos": [ 0, 0, 0 ] }, "sub": { "part": { "include": "parts/part.json" } } }
+    }"#).unwrap();
+
+    WorldObj::load(dir.join("cell.json")).unwrap().save(dir.join("cell_saved.json")).unwrap();
+    assert!(std::fs::read_to_string(dir.join("cell_saved.json")).unwrap().contains(r#""file": "parts/part.obj""#));
+    WorldObj::load(dir.join("cell_saved.json")).unwrap();
+
+    let sphere = WorldObj::zero().with_geometry(Geometry::new(Shape::Sphere { radius: 5.0 }).with_usage(GeometryUsage::Visual));
+    let json = serde_json::to_string(&sphere).unwrap();
+    assert_eq!(serde_json::from_str::<WorldObj>(&json).unwrap().geometry, sphere.geometry);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn pose_interpolation() {
+    use crate::rcs::math::{interpolate_pose, split_pose, PoseArc, PoseInterpolator};
+
+    let a = Position::new(0.0, 0.0, 0.0);
+    let b = Position::new_ori(Vec3::new(10.0, 0.0, 0.0), Mat3::from_rotation_z(PI / 2.0));
+
+    let mid = interpolate_pose(&a, &b, 0.5);
+    assert!((*mid.pos() - Vec3::new(5.0, 0.0, 0.0)).length() < 1e-5);
+    assert!(mid.ori().abs_diff_eq(Mat3::from_rotation_z(PI / 4.0), 1e-5));
+
+    // The rotation requires more steps than the distance
+    let steps = split_pose(&a, &b, 5.0, PI / 8.0);
+    assert_eq!(steps.len(), 5);
+    assert!(steps[4].ori().abs_diff_eq(*b.ori(), 1e-5));
+
+    // Half circle from (10, 0) over (0, 10) to (-10, 0)
+    let arc = PoseArc::new(Position::new(10.0, 0.0, 0.0), Vec3::new(0.0, 10.0, 0.0), Position::new_ori(Vec3::new(-10.0, 0.0, 0.0), *b.ori()));
+    assert!((arc.length() - 10.0 * PI).abs() < 1e-3);
+    assert!((*arc.pose_at(0.5).pos() - Vec3::new(0.0, 10.0, 0.0)).length() < 1e-3, "Bad arc {:?}", arc.pose_at(0.5));
+    assert!((*arc.pose_at(1.0).pos() - Vec3::new(-10.0, 0.0, 0.0)).length() < 1e-3);
+    assert!(arc.pose_at(0.5).ori().abs_diff_eq(Mat3::from_rotation_z(PI / 4.0), 1e-5));
+
+    // Collinear points result in a linear movement
+    let line = PoseArc::new(a.clone(), Vec3::new(5.0, 0.0, 0.0), b.clone());
+    assert!((*line.pose_at(0.5).pos() - Vec3::new(5.0, 0.0, 0.0)).length() < 1e-5);
+
+    let path = PoseInterpolator::new(vec![ a.clone(), b.clone(), Position::new(10.0, 30.0, 0.0) ]).unwrap();
+    assert_eq!(path.total_weight(), 40.0);
+    assert!((*path.pose_at(0.5).pos() - Vec3::new(10.0, 10.0, 0.0)).length() < 1e-4);
+    assert_eq!(path.sample(5).len(), 5);
+
+    // Pure rotations are weighted equally if there is no distance
+    let rot = PoseInterpolator::new(vec![ a.clone(), Position::new_ori(Vec3::ZERO, Mat3::from_rotation_x(1.0)) ]).unwrap();
+    assert!(rot.pose_at(0.5).ori().abs_diff_eq(Mat3::from_rotation_x(0.5), 1e-5));
+    assert!(PoseInterpolator::new(Vec::new()).is_err());
+}
+
+#[test]
+fn linear_solver() {
+    use crate::rcs::math::solve_linear;
+
+    // Requires a row swap, the first pivot is zero
+    let x = solve_linear(vec![ vec![ 0.0, 2.0 ], vec![ 1.0, 1.0 ] ], vec![ 4.0, 3.0 ]).unwrap();
+    assert!((x[0] - 1.0).abs() < 1e-12 && (x[1] - 2.0).abs() < 1e-12);
+
+    assert!(solve_linear(vec![ vec![ 1.0, 2.0 ], vec![ 2.0, 4.0 ] ], vec![ 1.0, 2.0 ]).is_none());
+}
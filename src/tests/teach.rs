@@ -0,0 +1,193 @@
+use core::f32::consts::PI;
+
+use glam::{Mat3, Vec3};
+use syunit::*;
+
+use crate::Descriptor;
+use crate::config::{ArmConfig, Elbow, Shoulder, Wrist};
+use crate::desc::{KinElement, Kinematic, Movement, SerialKinematic};
+use crate::desc::common::{SerialDescriptor, SixAxisDescriptor};
+use crate::rcs::{Point, PointRef, Position, WorldObj};
+use crate::tests::ARM_DIMS;
+use crate::teach::{FrameTeacher, PositionLibrary, TaughtFrame, TaughtPosition};
+
+fn arm_with_pallet() -> SixAxisDescriptor {
+    let mut desc = SixAxisDescriptor::new(ARM_DIMS);
+    desc.world_obj_mut().add_point("pallet", PointRef::new(
+        WorldObj::from_pos(Position::new_ori(Vec3::new(400.0, 0.0, 100.0), Mat3::from_rotation_z(PI / 4.0)))
+    )).unwrap();
+    desc
+}
+
+#[test]
+fn position_library() {
+    let mut desc = arm_with_pallet();
+    let phis = [ Phi(0.2), Phi(0.3), Phi(-0.2), Phi(0.5), Phi(0.8), Phi(-0.4) ];
+    desc.kinematic_mut().update(&phis).unwrap();
+    *desc.axis_config_mut() = ArmConfig::new(Shoulder::Front, Elbow::Up, Wrist::NoFlip);
+
+    let mut lib = PositionLibrary::new();
+    lib.teach("pick", &desc, Some("pallet")).unwrap();
+    lib.teach("home", &desc, None).unwrap();
+    lib.insert("park", TaughtPosition::joints([ Phi::ZERO; 6 ]));
+    assert!(lib.teach("bad", &desc, Some("pallet2")).is_err());
+    assert_eq!(lib.len(), 3);
+
+    let taught = lib.req("pick").unwrap();
+    assert_eq!(taught.pos.as_ref().unwrap().frame.as_deref(), Some("pallet"));
+    assert_eq!(taught.phis.as_deref(), Some(&phis[..]));
+
+    // Persistence
+    let path = std::env::temp_dir().join(format!("sybot_library_{}.json", std::process::id()));
+    lib.save(&path).unwrap();
+    let loaded : PositionLibrary<ArmConfig> = PositionLibrary::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), [ "home", "park", "pick" ]);
+
+    // Targets by name
+    let mut desc = arm_with_pallet();
+    let pick = loaded.phis("pick", &mut desc).unwrap();
+    assert!(pick.iter().zip(phis).all(|(a, b)| (a.0 - b.0).abs() < 1e-3), "Bad phis {:?}", pick);
+    assert_eq!(loaded.phis("park", &mut desc).unwrap(), [ Phi::ZERO; 6 ]);
+    assert!(loaded.phis("missing", &mut desc).is_err());
+
+    // Cartesian positions require the tool they have been taught with, joint positions do not
+    desc.kinematic_mut().set_tool(Position::new(0.0, 0.0, 50.0));
+    assert!(loaded.phis("pick", &mut desc).unwrap_err().to_string().contains("different tool"));
+    assert!(loaded.phis("park", &mut desc).is_ok());
+    desc.kinematic_mut().set_tool(Position::zero());
+
+    // Moving the frame moves the taught position with it
+    desc.world_obj().point("pallet").unwrap().borrow_mut().shift(Vec3::new(0.0, 0.0, 20.0));
+    let moved = loaded.phis("pick", &mut desc).unwrap();
+    desc.kinematic_mut().update(&moved).unwrap();
+
+    let mut reference = arm_with_pallet();
+    reference.kinematic_mut().update(&phis).unwrap();
+    assert!((desc.tcp().pos() - reference.tcp().pos() - Vec3::new(0.0, 0.0, 20.0)).length() < 1e-2);
+}
+
+/// A linear XY-descriptor whose TCP is the point of the last segment (not detached)
+fn linear_xy_attached() -> SerialDescriptor<2> {
+    let wobj = WorldObj::zero()
+        .add_point_inline("x", PointRef::new(
+            WorldObj::zero().add_point_inline("y", PointRef::new(Position::zero()))
+        ));
+
+    let kin = SerialKinematic::new([
+        KinElement::new(Movement::Linear(Vec3::X), wobj.point("x").unwrap()),
+        KinElement::new(Movement::Linear(Vec3::Y), wobj.point("x/y").unwrap())
+    ]);
+
+    SerialDescriptor::new(wobj, kin)
+}
+
+#[test]
+fn teach_attached_tcp() {
+    let mut desc = linear_xy_attached();
+    assert!(!desc.kinematic().is_tcp_detached());
+
+    desc.kinematic_mut().set_tool(Position::new(0.0, 0.0, -10.0));
+    desc.kinematic_mut().update(&[ Phi(5.0), Phi(3.0) ]).unwrap();
+
+    let mut lib : PositionLibrary<()> = PositionLibrary::new();
+    lib.teach("p", &desc, None).unwrap();
+
+    let taught = lib.get("p").unwrap().pos.as_ref().unwrap();
+    assert!((*taught.pos.pos() - Vec3::new(5.0, 3.0, -10.0)).length() < 1e-5, "Bad taught position {:?}", taught);
+
+    // Recalled from the stored pose only
+    desc.kinematic_mut().update(&[ Phi::ZERO; 2 ]).unwrap();
+    let mut cartesian : PositionLibrary<()> = PositionLibrary::new();
+    cartesian.insert("p", TaughtPosition { phis: None, ..lib.get("p").unwrap().clone() });
+
+    let phis = cartesian.phis("p", &mut desc).unwrap();
+    assert!((phis[0].0 - 5.0).abs() < 1e-2 && (phis[1].0 - 3.0).abs() < 1e-2, "Bad phis {:?}", phis);
+}
+
+#[test]
+fn frame_attached_tcp() {
+    let mut desc = linear_xy_attached();
+    let mut teacher = FrameTeacher::new();
+
+    desc.kinematic_mut().update(&[ Phi(5.0), Phi(3.0) ]).unwrap();
+    teacher.teach_origin(&desc);
+    desc.kinematic_mut().update(&[ Phi(15.0), Phi(3.0) ]).unwrap();
+    teacher.teach_x_point(&desc);
+    desc.kinematic_mut().update(&[ Phi(5.0), Phi(13.0) ]).unwrap();
+    teacher.teach_xy_point(&desc);
+
+    assert_eq!(teacher.origin, Some(Vec3::new(5.0, 3.0, 0.0)));
+    let frame = teacher.finish().unwrap();
+    assert!((*frame.pose.pos() - Vec3::new(5.0, 3.0, 0.0)).length() < 1e-5, "Bad frame {:?}", frame);
+}
+
+#[test]
+fn three_point_frame() {
+    let mut desc = arm_with_pallet();
+    let pallet = desc.world_obj().world_pose("pallet").unwrap();
+
+    let mut teacher = FrameTeacher::new();
+    assert!(teacher.finish().is_err());
+
+    // Touch the corners of the pallet, the XY-point is slightly off the Y-axis
+    for (point, teach) in [
+        (Vec3::new(0.0, 0.0, 0.0), FrameTeacher::teach_origin::<6, SixAxisDescriptor> as fn(&mut FrameTeacher, &SixAxisDescriptor)),
+        (Vec3::new(200.0, 0.0, 0.0), FrameTeacher::teach_x_point),
+        (Vec3::new(2.0, 150.0, 0.0), FrameTeacher::teach_xy_point)
+    ] {
+        let target = Position::new_ori(*pallet.pos() + *pallet.ori() * point, Mat3::from_rotation_x(PI));
+        let phis = desc.phis_for_pos(target).unwrap();
+        desc.kinematic_mut().update(&phis).unwrap();
+        teach(&mut teacher, &desc);
+    }
+
+    let frame = teacher.finish_into(&mut desc, "pallet/corner").unwrap();
+    assert!((frame.ortho_error - (2.0f32 / 150.0).atan()).abs() < 1e-3, "Bad error {}", frame.ortho_error);
+
+    // The error does not depend on the side the XY-point deviates to
+    for xy_point in [ Vec3::new(2.0, 150.0, 0.0), Vec3::new(-2.0, 150.0, 0.0) ] {
+        let skewed = TaughtFrame::from_points(Vec3::ZERO, Vec3::new(200.0, 0.0, 0.0), xy_point).unwrap();
+        assert!((skewed.ortho_error - (2.0f32 / 150.0).atan()).abs() < 1e-5, "Bad error {}", skewed.ortho_error);
+    }
+
+    let corner = desc.world_obj().relative_pose("pallet", "pallet/corner").unwrap();
+    assert!(corner.pos().length() < 1e-2, "Bad corner {:?}", corner);
+    assert!(corner.ori().abs_diff_eq(Mat3::IDENTITY, 1e-3), "Bad corner {:?}", corner);
+
+    // Re-teaching keeps the points attached to the frame
+    desc.world_obj().point("pallet/corner").unwrap().borrow_mut().as_wo_mut().unwrap()
+        .add_point("slot", PointRef::new(Position::new(10.0, 0.0, 0.0))).unwrap();
+    let shifted = TaughtFrame::from_points(Vec3::new(0.0, 0.0, 10.0), Vec3::new(1.0, 0.0, 10.0), Vec3::new(0.0, 1.0, 10.0)).unwrap();
+    assert!(shifted.ortho_error.abs() < 1e-6);
+    shifted.insert(desc.world_obj_mut(), "pallet/corner").unwrap();
+    assert!((*desc.world_obj().world_pose("pallet/corner/slot").unwrap().pos() - Vec3::new(10.0, 0.0, 10.0)).length() < 1e-3);
+
+    // Degenerate points
+    assert!(TaughtFrame::from_points(Vec3::ZERO, Vec3::ZERO, Vec3::Y).is_err());
+    assert!(TaughtFrame::from_points(Vec3::ZERO, Vec3::X, Vec3::X * 2.0).is_err());
+    assert!(shifted.insert(desc.world_obj_mut(), "missing/frame").is_err());
+}
+
+#[tokio::test]
+async fn move_to_named() {
+    use crate::Robot;
+    use crate::desc::common::LinearXYDescriptor;
+    use crate::tests::TestXYRobot;
+
+    let mut rob = TestXYRobot::new_simple();
+    let mut desc = LinearXYDescriptor::new();
+
+    let mut lib : PositionLibrary<()> = PositionLibrary::new();
+    lib.insert("pick", TaughtPosition::cartesian(Position::new(12.0, 4.0, 0.0)));
+    lib.insert("park", TaughtPosition::joints([ Phi(2.0), Phi(1.0) ]));
+
+    rob.move_to_named(&mut desc, &lib, "pick", Factor::MAX).await.unwrap();
+    assert!(rob.phis().iter().zip([ 12.0, 4.0 ]).all(|(a, b)| (a.0 - b).abs() < 0.1), "Bad phis {:?}", rob.phis());
+
+    rob.move_to_named(&mut desc, &lib, "park", Factor::MAX).await.unwrap();
+    assert!(rob.phis().iter().zip([ 2.0, 1.0 ]).all(|(a, b)| (a.0 - b).abs() < 0.1), "Bad phis {:?}", rob.phis());
+
+    assert!(rob.move_to_named(&mut desc, &lib, "missing", Factor::MAX).await.is_err());
+}